@@ -0,0 +1,310 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use pixel_sorter_better::sorter::{
+    AngledSorter, AvailableLineAlgos, AvailableSortAlgos, ColumnSorter, FrameSequence, PixelKey,
+    PixelScript, Preset, ScanPattern, ScanlineSorter, SortKey, Sorter, SpanSortConfig,
+    SpanSortMethod, TemporalSorter,
+};
+
+#[derive(Parser)]
+#[command(about = "Pixel sorting, with or without a window")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Sort a single image without opening the GUI
+    Sort(SortArgs),
+    /// Sort a folder of video frames along time, each pixel position across all frames is a line
+    SortSequence(SequenceArgs),
+}
+
+#[derive(Args)]
+pub struct SortArgs {
+    /// Image to read
+    pub input: PathBuf,
+    /// Where to write the sorted image, format is picked from the extension
+    pub output: PathBuf,
+
+    /// Load the line and sort settings from a JSON or RON preset instead of the flags below
+    #[arg(long)]
+    pub preset: Option<PathBuf>,
+    /// Write the settings used for this sort to a JSON or RON preset
+    #[arg(long)]
+    pub save_preset: Option<PathBuf>,
+
+    /// Line algorithm used to split the image into lines
+    #[arg(long, value_enum, default_value_t = LineAlgoArg::Scanline)]
+    pub line: LineAlgoArg,
+    /// Angle for the angled line algorithm, in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub angle: f32,
+    /// Blend the edges of angled lines to hide the staircase
+    #[arg(long)]
+    pub smooth_edges: bool,
+    /// Walk rows right to left and columns bottom to top
+    #[arg(long)]
+    pub reverse: bool,
+    /// How rows or columns are joined up
+    #[arg(long, value_enum, default_value_t = ScanPatternArg::Straight)]
+    pub pattern: ScanPatternArg,
+
+    #[command(flatten)]
+    pub span: SpanArgs,
+}
+
+#[derive(Args)]
+pub struct SequenceArgs {
+    /// Folder of frames to read, frames are ordered by the numbers in their file names
+    pub input: PathBuf,
+    /// Folder to write the sorted frames to, under their original names
+    pub output: PathBuf,
+
+    /// Load the sort settings from a JSON or RON preset instead of the flags below, the line
+    /// algorithm in it is ignored
+    #[arg(long)]
+    pub preset: Option<PathBuf>,
+    /// Sort from the last frame towards the first
+    #[arg(long)]
+    pub reverse: bool,
+
+    #[command(flatten)]
+    pub span: SpanArgs,
+}
+
+#[derive(Args)]
+pub struct SpanArgs {
+    /// Lower bound of the threshold, from 0 to 1
+    #[arg(long, default_value_t = 0.0)]
+    pub threshold_min: f32,
+    /// Upper bound of the threshold, from 0 to 1
+    #[arg(long, default_value_t = 1.0)]
+    pub threshold_max: f32,
+    /// Value the threshold is checked against
+    #[arg(long, value_enum, default_value_t = PixelKeyArg::Average)]
+    pub threshold_method: PixelKeyArg,
+    /// Sort the pixels outside the threshold instead of inside it
+    #[arg(long)]
+    pub invert_threshold: bool,
+    /// Value the pixels in a span are sorted by
+    #[arg(long, value_enum, default_value_t = PixelKeyArg::Average)]
+    pub sort_by: PixelKeyArg,
+    /// Sort from high to low
+    #[arg(long)]
+    pub descending: bool,
+    /// Key that orders pixels the previous keys see as equal, add ":desc" to sort it from high to
+    /// low, e.g. "--then-by hue --then-by value:desc"
+    #[arg(long, value_name = "KEY[:desc]", value_parser = parse_sort_key)]
+    pub then_by: Vec<SortKey>,
+    /// Keep pixels with equal keys in their original order
+    #[arg(long)]
+    pub stable: bool,
+    /// Where hue keys start counting from, in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub hue_origin: f32,
+    /// Rhai expression deciding which pixels are sorted, used instead of the threshold range,
+    /// e.g. "l > 60 && s > 100"
+    #[arg(long)]
+    pub threshold_script: Option<String>,
+    /// Rhai expression the pixels are sorted by, used instead of --sort-by,
+    /// e.g. "0.3 * r + 0.6 * g + 0.1 * b"
+    #[arg(long)]
+    pub sort_script: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LineAlgoArg {
+    Scanline,
+    Column,
+    Angled,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ScanPatternArg {
+    Straight,
+    Zigzag,
+    Snake,
+}
+
+impl From<ScanPatternArg> for ScanPattern {
+    fn from(pattern: ScanPatternArg) -> Self {
+        match pattern {
+            ScanPatternArg::Straight => ScanPattern::Straight,
+            ScanPatternArg::Zigzag => ScanPattern::ZigZag,
+            ScanPatternArg::Snake => ScanPattern::Snake,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PixelKeyArg {
+    Average,
+    Luminosity,
+    Red,
+    Green,
+    Blue,
+    Hue,
+    Saturation,
+    Lightness,
+    Value,
+    Chroma,
+    LabLightness,
+    LabA,
+    LabB,
+    OklabLightness,
+    OklchChroma,
+    OklchHue,
+}
+
+impl From<PixelKeyArg> for PixelKey {
+    fn from(key: PixelKeyArg) -> Self {
+        match key {
+            PixelKeyArg::Average => PixelKey::Average,
+            PixelKeyArg::Luminosity => PixelKey::Luminosity,
+            PixelKeyArg::Red => PixelKey::Red,
+            PixelKeyArg::Green => PixelKey::Green,
+            PixelKeyArg::Blue => PixelKey::Blue,
+            PixelKeyArg::Hue => PixelKey::Hue,
+            PixelKeyArg::Saturation => PixelKey::Saturation,
+            PixelKeyArg::Lightness => PixelKey::Lightness,
+            PixelKeyArg::Value => PixelKey::Value,
+            PixelKeyArg::Chroma => PixelKey::Chroma,
+            PixelKeyArg::LabLightness => PixelKey::LabLightness,
+            PixelKeyArg::LabA => PixelKey::LabA,
+            PixelKeyArg::LabB => PixelKey::LabB,
+            PixelKeyArg::OklabLightness => PixelKey::OklabLightness,
+            PixelKeyArg::OklchChroma => PixelKey::OklchChroma,
+            PixelKeyArg::OklchHue => PixelKey::OklchHue,
+        }
+    }
+}
+
+/// Reads a key given as `<key>` or `<key>:desc`.
+fn parse_sort_key(arg: &str) -> Result<SortKey, String> {
+    let (key, descending) = match arg.strip_suffix(":desc") {
+        Some(key) => (key, true),
+        None => (arg.strip_suffix(":asc").unwrap_or(arg), false),
+    };
+    let key = PixelKeyArg::from_str(key, true).map_err(|_| {
+        let keys = PixelKeyArg::value_variants()
+            .iter()
+            .filter_map(|key| key.to_possible_value())
+            .map(|key| key.get_name().to_string())
+            .collect::<Vec<_>>();
+        format!("unknown key \"{key}\", expected one of {}", keys.join(", "))
+    })?;
+
+    Ok(SortKey {
+        key: key.into(),
+        descending,
+    })
+}
+
+impl SortArgs {
+    fn line_algo(&self) -> AvailableLineAlgos {
+        match self.line {
+            LineAlgoArg::Scanline => AvailableLineAlgos::Scanline(ScanlineSorter {
+                reverse: self.reverse,
+                pattern: self.pattern.into(),
+            }),
+            LineAlgoArg::Column => AvailableLineAlgos::Column(ColumnSorter {
+                reverse: self.reverse,
+                pattern: self.pattern.into(),
+            }),
+            LineAlgoArg::Angled => AvailableLineAlgos::Angled(AngledSorter {
+                angle: self.angle,
+                smooth_edges: self.smooth_edges,
+            }),
+        }
+    }
+
+    fn preset(&self) -> Result<Preset, Box<dyn Error>> {
+        if let Some(ref path) = self.preset {
+            return Ok(Preset::load(path)?);
+        }
+
+        Ok(Preset {
+            line_algo: self.line_algo(),
+            sort_algo: self.span.sort_algo()?,
+        })
+    }
+}
+
+impl SpanArgs {
+    //The config has a private field in gui builds, so it can't be built with a struct literal.
+    #[allow(clippy::field_reassign_with_default)]
+    fn sort_algo(&self) -> Result<AvailableSortAlgos, Box<dyn Error>> {
+        let mut config = SpanSortConfig::default();
+        config.threshold = self.threshold_min..=self.threshold_max;
+        config.threshold_method = self.threshold_method.into();
+        config.invert_threshold = self.invert_threshold;
+        config.sorting_method = self.sort_by.into();
+        config.descending = self.descending;
+        config.tie_breakers = self.then_by.clone();
+        config.stable = self.stable;
+        config.hue_origin = self.hue_origin;
+        if let Some(ref source) = self.threshold_script {
+            config.threshold_method = PixelKey::Script;
+            config.threshold_script = PixelScript::new(source.as_str());
+            if let Some(error) = config.threshold_script.check(true) {
+                return Err(format!("Invalid threshold script: {error}").into());
+            }
+        }
+        if let Some(ref source) = self.sort_script {
+            config.sorting_method = PixelKey::Script;
+            config.sort_script = PixelScript::new(source.as_str());
+            if let Some(error) = config.sort_script.check(false) {
+                return Err(format!("Invalid sort script: {error}").into());
+            }
+        }
+
+        Ok(AvailableSortAlgos::SpanSort(SpanSortMethod { config }))
+    }
+}
+
+pub fn run_sort(args: SortArgs) -> Result<(), Box<dyn Error>> {
+    let preset = args.preset()?;
+    if let Some(ref path) = args.save_preset {
+        preset.save(path)?;
+    }
+
+    let mut image = image::open(&args.input)?.to_rgba8();
+
+    let start = Instant::now();
+    preset.line_algo.sort_image(&mut image, preset.sort_algo.clone());
+    println!("Sorting took {:?}", start.elapsed());
+    warn_script_error(&preset.sort_algo);
+
+    image.save(&args.output)?;
+    Ok(())
+}
+
+pub fn run_sort_sequence(args: SequenceArgs) -> Result<(), Box<dyn Error>> {
+    let sort_algo = match args.preset {
+        Some(ref path) => Preset::load(path)?.sort_algo,
+        None => args.span.sort_algo()?,
+    };
+
+    let mut sequence = FrameSequence::load(&args.input)?;
+
+    let start = Instant::now();
+    TemporalSorter { reverse: args.reverse }.sort_image(&mut sequence.frames, sort_algo.clone());
+    println!("Sorting {} frames took {:?}", sequence.frames.len(), start.elapsed());
+    warn_script_error(&sort_algo);
+
+    sequence.save(&args.output)?;
+    Ok(())
+}
+
+/// Scripts that fail partway through don't stop the sort, so say so afterwards.
+fn warn_script_error(sort_algo: &AvailableSortAlgos) {
+    if let Some(error) = sort_algo.script_error() {
+        eprintln!("Warning, a script failed while sorting: {error}");
+    }
+}
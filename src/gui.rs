@@ -1,32 +1,50 @@
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Instant;
 
 use eframe::emath::vec2;
 use eframe::Frame;
 use egui::{
-    Button, Checkbox, Color32, ColorImage, ComboBox, Context, Direction, Layout, Margin, Stroke,
-    TextureFilter, TextureHandle, TextureId, TextureOptions, Vec2,
+    Align2, Button, Checkbox, Color32, ColorImage, ComboBox, Context, Direction, DragValue, Layout,
+    Margin, Stroke, TextureFilter, TextureHandle, TextureId, TextureOptions, Ui, Vec2,
 };
 use egui::load::SizedTexture;
 use egui::panel::TopBottomSide;
-use image::{ImageBuffer, Rgba};
+use image::{ImageError, RgbaImage};
 
-use crate::sorter::{AngledSorter, Animateable, ScanlineSorter, Sorter, SortMethod};
-use crate::sorter::{AvailableLineAlgos, AvailableSortAlgos};
+use pixel_sorter_better::sorter::{AngledSorter, ColumnSorter, CurveSorter, EdgeSorter};
+use pixel_sorter_better::sorter::{RadialSorter, RingSorter, ScanlineSorter, SpiralSorter};
+use pixel_sorter_better::sorter::{FlowFieldSorter, PathSorter, TileSorter, VoronoiSorter};
+use pixel_sorter_better::sorter::{RegionSorter, WaveSorter};
+use pixel_sorter_better::sorter::{Sorter, SortMethod};
+use pixel_sorter_better::sorter::{AvailableLineAlgos, AvailableSortAlgos, Preset};
+use pixel_sorter_better::sorter::{Easing, FrameSequence, Project, Segment, TemporalSorter};
 
 #[derive(Default)]
 pub struct AppState {
-    original_image: Option<ColorImage>,
-    working_image: Option<ColorImage>,
+    image_path: Option<PathBuf>,
+    original_image: Option<RgbaImage>,
+    working_image: Option<RgbaImage>,
     image_handle: Option<TextureHandle>,
     selected_line_algo: AvailableLineAlgos,
     selected_sort_algo: AvailableSortAlgos,
     stack_changes: bool,
     live_sort: bool,
+    /// The settings the live preview last sorted with, as json, so it only sorts again once
+    /// they change.
+    previewed_settings: Option<String>,
+    /// Set while a sort runs in the background.
+    sorting: Arc<AtomicBool>,
+    /// Where a background sort with stacked changes leaves its image for the ui thread to pick up.
+    sorted_image: Arc<Mutex<Option<RgbaImage>>>,
     anim_mode: bool,
     pub sort_keyframes: Vec<AvailableSortAlgos>,
     pub line_keyframes: Vec<AvailableLineAlgos>,
+    pub keyframe_segments: Vec<Segment>,
+    /// Keyframe whose strokes or cells are edited by dragging on the image in animation mode.
+    edited_keyframe: Option<usize>,
 }
 
 impl AppState {
@@ -49,40 +67,98 @@ impl AppState {
         Self {
             sort_keyframes: vec![],
             line_keyframes: vec![],
+            keyframe_segments: vec![],
             ..Default::default()
         }
     }
 
     pub fn save_image(&mut self, path_buf: PathBuf) {
         if let Some(ref mut image) = self.working_image {
-            let [w, h] = image.size;
-            let pixels = image.as_raw();
-
-            let dyn_image =
-                ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(w as u32, h as u32, pixels).unwrap();
-            dyn_image.save(path_buf).unwrap();
+            image.save(path_buf).unwrap();
         }
     }
 
-    pub fn open_image(&mut self, path_buf: PathBuf) {
-        let image = image::open(path_buf).unwrap();
-        let size = [image.width() as _, image.height() as _];
-        let image_buffer = image.to_rgba8();
-        let pixels = image_buffer.as_flat_samples();
-        let color_image = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+    pub fn open_image(&mut self, path_buf: PathBuf) -> Result<(), ImageError> {
+        let image = image::open(&path_buf)?.to_rgba8();
         if let Some(ref mut texture) = self.image_handle {
-            texture.set(color_image.clone(), Default::default())
+            texture.set(to_color_image(&image), Default::default())
+        }
+        self.image_path = Some(path_buf);
+        self.original_image = Some(image.clone());
+        self.working_image = Some(image);
+        self.previewed_settings = None;
+        Ok(())
+    }
+
+    /// Asks for a folder of frames and a folder to write to, then sorts the frames along time
+    /// with the selected sorting algorithm in the background.
+    pub fn sort_sequence(&self) {
+        let Some(input) = rfd::FileDialog::new().set_title("Frames to sort").pick_folder() else {
+            return;
+        };
+        let output = rfd::FileDialog::new()
+            .set_title("Where to save the sorted frames")
+            .pick_folder();
+        let Some(output) = output else {
+            return;
+        };
+
+        let sort_algo = self.selected_sort_algo.clone();
+        thread::spawn(move || {
+            let result = FrameSequence::load(input).and_then(|mut sequence| {
+                TemporalSorter::default().sort_image(&mut sequence.frames, sort_algo);
+                sequence.save(output)
+            });
+            if let Err(error) = result {
+                eprintln!("Failed to sort sequence: {error}");
+            }
+        });
+    }
+
+    pub fn project(&self) -> Project {
+        let keyframes = self
+            .line_keyframes
+            .iter()
+            .zip(self.sort_keyframes.iter())
+            .map(|(line_algo, sort_algo)| Preset {
+                line_algo: line_algo.clone(),
+                sort_algo: sort_algo.clone(),
+            })
+            .collect();
+
+        Project {
+            image_path: self.image_path.clone(),
+            keyframes,
+            segments: self.keyframe_segments.clone(),
+        }
+    }
+
+    pub fn open_project(&mut self, project: Project) {
+        if let Some(path) = project.image_path {
+            if let Err(error) = self.open_image(path) {
+                eprintln!("Failed to open project image: {error}");
+            }
         }
-        self.original_image = Some(color_image.clone());
-        self.working_image = Some(color_image);
+
+        let (line_keyframes, sort_keyframes) = project
+            .keyframes
+            .into_iter()
+            .map(|keyframe| (keyframe.line_algo, keyframe.sort_algo))
+            .unzip();
+        self.line_keyframes = line_keyframes;
+        self.sort_keyframes = sort_keyframes;
+        self.keyframe_segments = project.segments;
+        self.edited_keyframe = None;
+        self.keyframe_segments
+            .resize(self.line_keyframes.len(), Segment::default());
     }
 
     pub fn load_texture(&mut self, ctx: &Context) {
-        if let Some(color_image) = self.original_image.clone() {
+        if let Some(ref image) = self.original_image {
             self.image_handle.get_or_insert_with(|| {
                 ctx.load_texture(
                     "image",
-                    color_image.clone(),
+                    to_color_image(image),
                     TextureOptions {
                         magnification: TextureFilter::Nearest,
                         minification: TextureFilter::Nearest,
@@ -93,27 +169,54 @@ impl AppState {
         }
     }
 
+    /// Sorts a copy of the working image on another thread, so slow sorts don't hold up the ui,
+    /// and shows it once it's done. With `keep` the result also becomes the working image.
+    ///
+    /// Only one sort runs at a time, returns whether this one got started.
+    fn sort_in_background(&self, ctx: &Context, keep: bool) -> bool {
+        let (Some(image), Some(mut texture)) = (&self.working_image, self.image_handle.clone())
+        else {
+            return false;
+        };
+        if self.sorting.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+
+        let mut image = image.clone();
+        let line_algo = self.selected_line_algo.clone();
+        let sort_algo = self.selected_sort_algo.clone();
+        let sorting = self.sorting.clone();
+        let sorted_image = self.sorted_image.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            line_algo.sort_image(&mut image, sort_algo);
+            texture.set(to_color_image(&image), Default::default());
+            println!("Sorting took {:?}", start.elapsed());
+
+            if keep {
+                *sorted_image.lock().unwrap() = Some(image);
+            }
+            sorting.store(false, Ordering::Release);
+            ctx.request_repaint();
+        });
+        true
+    }
+
     pub fn sorter_ui(&mut self, ctx: &Context) {
+        if let Some(image) = self.sorted_image.lock().unwrap().take() {
+            self.working_image = Some(image);
+        }
+
         egui::SidePanel::left("settings_panel").show(ctx, |ui| {
             ui.with_layout(Layout::default(), |ui| {
                 ui.add_space(3.0);
-                let ref mut line_algo = self.selected_line_algo;
-                let ref mut sort_algo = self.selected_sort_algo;
+                let line_algo = &mut self.selected_line_algo;
+                let sort_algo = &mut self.selected_sort_algo;
 
                 ComboBox::from_label("Line algorithm")
                     .selected_text(format!("{:?}", line_algo))
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(
-                            line_algo,
-                            AvailableLineAlgos::Scanline(ScanlineSorter),
-                            "Horizontal Lines",
-                        );
-                        ui.selectable_value(
-                            line_algo,
-                            AvailableLineAlgos::Angled(AngledSorter { angle: 0.0 }),
-                            "Angled Lines",
-                        );
-                    });
+                    .show_ui(ui, |ui| line_algo_options(ui, line_algo));
 
                 new_config_frame().show(ui, |ui| {
                     line_algo.ui(ui);
@@ -137,45 +240,47 @@ impl AppState {
                     ui.allocate_space(egui::vec2(ui.available_width(), 0.0));
                 });
 
-                let mut option_line_alg = Some(line_algo.clone());
-                let mut sorter_image = self.working_image.as_mut();
+                ui.horizontal(|ui| {
+                    if ui.button("Save preset").clicked() {
+                        let task = rfd::FileDialog::new()
+                            .add_filter("Preset", &["json", "ron"])
+                            .save_file();
+                        if let Some(file) = task {
+                            let preset = Preset {
+                                line_algo: line_algo.clone(),
+                                sort_algo: sort_algo.clone(),
+                            };
+                            if let Err(error) = preset.save(file) {
+                                eprintln!("Failed to save preset: {error}");
+                            }
+                        }
+                    }
+
+                    if ui.button("Load preset").clicked() {
+                        let task = rfd::FileDialog::new()
+                            .add_filter("Preset", &["json", "ron"])
+                            .pick_file();
+                        if let Some(file) = task {
+                            match Preset::load(file) {
+                                Ok(preset) => {
+                                    *line_algo = preset.line_algo;
+                                    *sort_algo = preset.sort_algo;
+                                }
+                                Err(error) => eprintln!("Failed to load preset: {error}"),
+                            }
+                        }
+                    }
+                });
 
-                ui.add_enabled_ui(!self.live_sort, |ui| {
+                let sorting = self.sorting.load(Ordering::Acquire);
+                ui.add_enabled_ui(!self.live_sort && !sorting, |ui| {
                     let button =
                         Button::new("Sort!").min_size(Vec2::new(ui.available_width(), 10.0));
                     if ui
                         .add_sized(egui::vec2(ui.available_width(), 10.0), button)
                         .clicked()
                     {
-                        if let Some(mut texture_handle) = self.image_handle.clone() {
-                            if let Some(line_algorithm) = option_line_alg.take() {
-                                if let Some(mut sorter_image) = sorter_image.take() {
-                                    if self.stack_changes {
-                                        let t_sort_alg = sort_algo.clone();
-                                        thread::scope(|scope| {
-                                            scope.spawn(move || {
-                                                let start = Instant::now();
-
-                                                line_algorithm
-                                                    .sort_image(&mut sorter_image, t_sort_alg);
-                                                texture_handle
-                                                    .set(sorter_image.clone(), Default::default());
-
-                                                println!("Sorting took {:?}", start.elapsed());
-                                            });
-                                        });
-                                    }else{
-                                        let t_sort_alg = sort_algo.clone();
-                                        let mut sorter_image = sorter_image.clone();
-                                        thread::scope(|scope| {
-                                            scope.spawn(move || {
-                                                line_algorithm.sort_image(&mut sorter_image, t_sort_alg);
-                                                texture_handle.set(sorter_image.clone(), Default::default());
-                                            });
-                                        });                                    }
-                                }
-                            }
-                        }
+                        self.sort_in_background(ui.ctx(), self.stack_changes);
                     }
                 });
 
@@ -186,21 +291,16 @@ impl AppState {
                 ui.add_sized(egui::vec2(ui.available_width(), 10.0), checkbox);
 		}); 
 
-		if self.live_sort {
-                    if let Some(line_algorithm) = option_line_alg.take() {
-                        if let Some(sorter_image) = sorter_image.take() {
-                            let mut texture_handle = self.image_handle.clone().unwrap();
-                            let t_sort_alg = sort_algo.clone();
-                            //cloning this because otherwise live sorting will just mess the image up immediately.
-                            let mut sorter_image = sorter_image.clone();
-                            thread::scope(|scope| {
-                                scope.spawn(move || {
-                                    line_algorithm.sort_image(&mut sorter_image, t_sort_alg);
-                                    texture_handle.set(sorter_image.clone(), Default::default());
-                                });
-                            });
-                        }
+                if self.live_sort {
+                    //Only sort again once the settings changed, and once the last preview is done.
+                    let settings = (&self.selected_line_algo, &self.selected_sort_algo);
+                    let settings = serde_json::to_string(&settings).ok();
+                    let changed = settings != self.previewed_settings;
+                    if changed && self.sort_in_background(ui.ctx(), false) {
+                        self.previewed_settings = settings;
                     }
+                } else {
+                    self.previewed_settings = None;
                 }
             });
         });
@@ -216,7 +316,9 @@ impl eframe::App for AppState {
                 if ui.button("Open").clicked() {
                     let task = rfd::FileDialog::new().pick_file();
                     if let Some(file) = task {
-                        self.open_image(file);
+                        if let Err(error) = self.open_image(file) {
+                            eprintln!("Failed to open image: {error}");
+                        }
                     }
                 }
 
@@ -230,11 +332,15 @@ impl eframe::App for AppState {
                 if ui.button("Reset Image").clicked() {
                     if let Some(ref mut texture) = self.image_handle {
                         if let Some(ref mut image) = self.original_image {
-                            texture.set(image.clone(), Default::default());
+                            texture.set(to_color_image(image), Default::default());
                             self.working_image = Some(image.clone());
                         }
                     }
                 }
+                let sequence_button = ui.button("Sort Sequence");
+                if sequence_button.on_hover_text("Sort a folder of frames along time").clicked() {
+                    self.sort_sequence();
+                }
                 if ui.button("Toggle animation mode").clicked() {
                     self.anim_mode = !self.anim_mode;
                 }
@@ -245,16 +351,43 @@ impl eframe::App for AppState {
             self.sorter_ui(ctx)
         } else {
             egui::SidePanel::left("settings_panel").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Open Project").clicked() {
+                        let task = rfd::FileDialog::new()
+                            .add_filter("Project", &["json", "ron"])
+                            .pick_file();
+                        if let Some(file) = task {
+                            match Project::load(file) {
+                                Ok(project) => self.open_project(project),
+                                Err(error) => eprintln!("Failed to open project: {error}"),
+                            }
+                        }
+                    }
+
+                    if ui.button("Save Project").clicked() {
+                        let task = rfd::FileDialog::new()
+                            .add_filter("Project", &["json", "ron"])
+                            .save_file();
+                        if let Some(file) = task {
+                            if let Err(error) = self.project().save(file) {
+                                eprintln!("Failed to save project: {error}");
+                            }
+                        }
+                    }
+                });
+
                 egui::ScrollArea::vertical()
                     .max_height(ui.available_height() - 50.0)
                     .show(ui, |ui| {
                         ui.with_layout(Layout::default(), |ui| {
                             ui.add_space(3.0);
                             let mut to_remove = Vec::new();
-                            for (i, (line_algo, sort_algo)) in self
+                            let keyframe_count = self.line_keyframes.len();
+                            for (i, ((line_algo, sort_algo), segment)) in self
                                 .line_keyframes
                                 .iter_mut()
                                 .zip(self.sort_keyframes.iter_mut())
+                                .zip(self.keyframe_segments.iter_mut())
                                 .enumerate()
                             {
                                 ui.add_space(3.0);
@@ -274,6 +407,18 @@ impl eframe::App for AppState {
                                                 to_remove.push(i);
                                             }
                                             button_response.on_hover_text("Remove this frame");
+
+                                            let editing = self.edited_keyframe == Some(i);
+                                            let edit_response =
+                                                ui.selectable_label(editing, "Edit on image");
+                                            if edit_response.clicked() {
+                                                self.edited_keyframe =
+                                                    if editing { None } else { Some(i) };
+                                            }
+                                            edit_response.on_hover_text(
+                                                "Drag on the image to change this frame's \
+                                                 strokes or cells",
+                                            );
                                         });
                                     });
 
@@ -288,20 +433,7 @@ impl eframe::App for AppState {
                                     .show(ui, |ui| {
                                         ComboBox::new(format!("{i}-linalg"), "Line algorithm")
                                             .selected_text(format!("{:?}", line_algo))
-                                            .show_ui(ui, |ui| {
-                                                ui.selectable_value(
-                                                    line_algo,
-                                                    AvailableLineAlgos::Scanline(ScanlineSorter),
-                                                    "Horizontal Lines",
-                                                );
-                                                ui.selectable_value(
-                                                    line_algo,
-                                                    AvailableLineAlgos::Angled(AngledSorter {
-                                                        angle: 0.0,
-                                                    }),
-                                                    "Angled Lines",
-                                                );
-                                            });
+                                            .show_ui(ui, |ui| line_algo_options(ui, line_algo));
 
                                         new_config_frame().show(ui, |ui| {
                                             line_algo.ui(ui);
@@ -330,12 +462,39 @@ impl eframe::App for AppState {
                                                 0.0,
                                             ));
                                         });
+
+                                        if i + 1 < keyframe_count {
+                                            ui.separator();
+
+                                            ui.horizontal(|ui| {
+                                                ui.add(
+                                                    DragValue::new(&mut segment.frames)
+                                                        .clamp_range(1..=10000)
+                                                        .suffix(" frames"),
+                                                );
+                                                ComboBox::new(format!("{i}-easing"), "to next frame")
+                                                    .selected_text(segment.easing.to_string())
+                                                    .show_ui(ui, |ui| {
+                                                        for easing in Easing::ALL {
+                                                            ui.selectable_value(
+                                                                &mut segment.easing,
+                                                                easing,
+                                                                easing.to_string(),
+                                                            );
+                                                        }
+                                                    });
+                                            });
+                                        }
                                     });
                             }
 
+                            if !to_remove.is_empty() {
+                                self.edited_keyframe = None;
+                            }
                             for i in to_remove {
                                 self.sort_keyframes.remove(i);
                                 self.line_keyframes.remove(i);
+                                self.keyframe_segments.remove(i);
                             }
 
                             let add_keyframe_button = Button::new("+")
@@ -347,6 +506,7 @@ impl eframe::App for AppState {
                             {
                                 self.sort_keyframes.push(AvailableSortAlgos::default());
                                 self.line_keyframes.push(AvailableLineAlgos::default());
+                                self.keyframe_segments.push(Segment::default());
                             }
                         });
                     });
@@ -359,41 +519,17 @@ impl eframe::App for AppState {
                     .clicked()
                 {
                     if let Some(mut texture) = self.image_handle.clone() {
-                        let mut sort_keyframes = self.sort_keyframes.clone();
-                        let mut line_keyframes = self.line_keyframes.clone();
+                        let project = self.project();
 
                         let image = self.working_image.clone().unwrap();
 
                         thread::spawn(move || {
-                            let mut current_sort = sort_keyframes.remove(0);
-                            let mut current_line = line_keyframes.remove(0);
-                            while !line_keyframes.is_empty() {
-                                let mut target_sort = sort_keyframes.remove(0);
-                                let mut target_line = line_keyframes.remove(0);
-                                {
-                                    while current_sort != target_sort {
-                                        current_sort = target_sort;
-                                        target_sort = sort_keyframes.remove(0);
-                                    }
-                                    while current_line != target_line {
-                                        current_line = target_line;
-                                        target_line = line_keyframes.remove(0);
-                                    }
-                                }
-                                let frames = 360.0;
-                                for i in 0..frames as u16 {
-                                    let t = cubic_ease(i as f32 / frames);
-
-                                    let mut current_sort = current_sort.clone();
-                                    let mut current_line = current_line.clone();
-
-                                    let mut sorting_image = image.clone();
-                                    current_sort.lerp(&target_sort, t);
-                                    current_line.lerp(&target_line, t);
-                                    current_line
-                                        .sort_image(&mut sorting_image, current_sort.clone());
-                                    texture.set(sorting_image.clone(), Default::default());
-                                }
+                            for frame in project.frames() {
+                                let mut sorting_image = image.clone();
+                                frame
+                                    .line_algo
+                                    .sort_image(&mut sorting_image, frame.sort_algo);
+                                texture.set(to_color_image(&sorting_image), Default::default());
                             }
                         });
                     }
@@ -408,53 +544,20 @@ impl eframe::App for AppState {
                     let task = rfd::FileDialog::new().pick_folder();
                     if let Some(folder) = task {
                         if let Some(mut texture) = self.image_handle.clone() {
-                            let mut sort_keyframes = self.sort_keyframes.clone();
-                            let mut line_keyframes = self.line_keyframes.clone();
+                            let project = self.project();
 
                             let image = self.working_image.clone().unwrap();
 
                             thread::spawn(move || {
-                                let mut file_name = 0;
-                                let mut current_sort = sort_keyframes.remove(0);
-                                let mut current_line = line_keyframes.remove(0);
-                                while !line_keyframes.is_empty() {
-                                    let mut target_sort = sort_keyframes.remove(0);
-                                    let mut target_line = line_keyframes.remove(0);
-                                    {
-                                        while current_sort != target_sort {
-                                            current_sort = target_sort;
-                                            target_sort = sort_keyframes.remove(0);
-                                        }
-                                        while current_line != target_line {
-                                            current_line = target_line;
-                                            target_line = line_keyframes.remove(0);
-                                        }
-                                    }
-                                    let frames = 720.0;
-                                    for i in 0..frames as u16 {
-
-                                        let mut current_sort = current_sort.clone();
-                                        let mut current_line = current_line.clone();
-
-                                        let t = cubic_ease(i as f32 / frames);
-                                        let mut sorting_image = image.clone();
-                                        current_sort.lerp(&target_sort, t);
-                                        current_line.lerp(&target_line, t);
-                                        current_line
-                                            .sort_image(&mut sorting_image, current_sort.clone());
-                                        texture.set(sorting_image.clone(), Default::default());
-                                        let file = folder.clone().join(format!("{:0>5}.png", file_name));
-
-                                        let [w, h] = sorting_image.size;
-                                        let pixels = sorting_image.as_raw();
-
-                                        let dyn_image =
-                                            ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(w as u32, h as u32, pixels).unwrap();
-                                        dyn_image.save(file).unwrap();
-
+                                for (file_name, frame) in project.frames().enumerate() {
+                                    let mut sorting_image = image.clone();
+                                    frame
+                                        .line_algo
+                                        .sort_image(&mut sorting_image, frame.sort_algo);
+                                    texture.set(to_color_image(&sorting_image), Default::default());
+                                    let file = folder.clone().join(format!("{:0>5}.png", file_name));
 
-                                        file_name += 1;
-                                    }
+                                    sorting_image.save(file).unwrap();
                                 }
                             });
                         }
@@ -472,11 +575,28 @@ impl eframe::App for AppState {
                 let size = egui::vec2(width, width / texture.aspect_ratio());
 
                 ui.with_layout(Layout::centered_and_justified(Direction::TopDown), |ui| {
-                    ui.image(SizedTexture {
+                    let mut response = ui.image(SizedTexture {
                         id: TextureId::from(texture),
                         size,
-                    })
-                        .mark_changed();
+                    });
+                    response.mark_changed();
+                    //The response fills the whole panel, the texture is only painted in the middle.
+                    let image_rect =
+                        Align2::CENTER_CENTER.align_size_within_rect(size, response.rect);
+
+                    let line_algo = if self.anim_mode {
+                        self.edited_keyframe
+                            .and_then(|i| self.line_keyframes.get_mut(i))
+                    } else {
+                        Some(&mut self.selected_line_algo)
+                    };
+                    match line_algo {
+                        Some(AvailableLineAlgos::Path(path)) => path.edit_strokes(ui, image_rect),
+                        Some(AvailableLineAlgos::Voronoi(voronoi)) => {
+                            voronoi.edit_cells(ui, image_rect)
+                        }
+                        _ => {}
+                    }
                 });
             }
         });
@@ -484,8 +604,82 @@ impl eframe::App for AppState {
     }
 }
 
-fn cubic_ease(t: f32) -> f32 {
-    t * t * (3.0 - 2.0 * t)
+fn to_color_image(image: &RgbaImage) -> ColorImage {
+    let size = [image.width() as _, image.height() as _];
+    ColorImage::from_rgba_unmultiplied(size, image.as_raw())
+}
+
+fn line_algo_options(ui: &mut Ui, line_algo: &mut AvailableLineAlgos) {
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Scanline(ScanlineSorter::default()),
+        "Horizontal Lines",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Column(ColumnSorter::default()),
+        "Vertical Lines",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Angled(AngledSorter::default()),
+        "Angled Lines",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Radial(RadialSorter::default()),
+        "Radial Lines",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Rings(RingSorter::default()),
+        "Rings",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Spiral(SpiralSorter::default()),
+        "Spiral",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Wave(WaveSorter::default()),
+        "Waves",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::FlowField(FlowFieldSorter::default()),
+        "Flow field",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Edges(EdgeSorter::default()),
+        "Follow edges",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Curve(CurveSorter::default()),
+        "Space-filling curve",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Path(PathSorter::default()),
+        "Drawn paths",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Voronoi(VoronoiSorter::default()),
+        "Voronoi cells",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Tiles(TileSorter::default()),
+        "Tiles",
+    );
+    ui.selectable_value(
+        line_algo,
+        AvailableLineAlgos::Regions(RegionSorter::default()),
+        "Colour regions",
+    );
 }
 
 pub fn new_config_frame() -> egui::containers::Frame {
@@ -1,17 +1,45 @@
-#![feature(slice_split_at_unchecked, vec_into_raw_parts)]
+use clap::Parser;
 
-use crate::gui::AppState;
+use crate::cli::{Cli, Command};
 
+mod cli;
+#[cfg(feature = "gui")]
 mod gui;
-mod sorter;
 
 fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Sort(args)) => {
+            if let Err(error) = cli::run_sort(args) {
+                eprintln!("Failed to sort image: {error}");
+                std::process::exit(1);
+            }
+        }
+        Some(Command::SortSequence(args)) => {
+            if let Err(error) = cli::run_sort_sequence(args) {
+                eprintln!("Failed to sort sequence: {error}");
+                std::process::exit(1);
+            }
+        }
+        None => run_gui(),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn run_gui() {
     let native_options = eframe::NativeOptions::default();
 
     eframe::run_native(
         "Pixel Sorter",
         native_options,
-        Box::new(|cc| Box::new(AppState::new(cc))),
+        Box::new(|cc| Box::new(gui::AppState::new(cc))),
     )
     .expect("TODO: panic message");
 }
+
+#[cfg(not(feature = "gui"))]
+fn run_gui() {
+    eprintln!("This build doesn't include the GUI, use the `sort` subcommand instead.");
+    std::process::exit(1);
+}
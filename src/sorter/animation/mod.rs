@@ -1,3 +1,6 @@
+mod project;
+pub use project::*;
+
 pub trait Animateable {
     fn lerp(&mut self, target: &Self, weight: f32);
 }
@@ -0,0 +1,91 @@
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::sorter::{load_file, save_file, Animateable, Preset, PresetError};
+
+/// Everything needed to render an animation again: the image and every keyframe.
+///
+/// `segments[i]` describes the transition from `keyframes[i]` to `keyframes[i + 1]`, so the
+/// last segment is never played.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Project {
+    pub image_path: Option<PathBuf>,
+    pub keyframes: Vec<Preset>,
+    pub segments: Vec<Segment>,
+}
+
+impl Project {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PresetError> {
+        load_file(path.as_ref())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PresetError> {
+        save_file(self, path.as_ref())
+    }
+
+    /// The settings for every frame of the animation, in order.
+    pub fn frames(&self) -> impl Iterator<Item = Preset> + '_ {
+        self.keyframes
+            .windows(2)
+            .zip(&self.segments)
+            .flat_map(|(pair, segment)| {
+                let (from, to) = (&pair[0], &pair[1]);
+                (0..segment.frames).map(move |i| {
+                    let mut frame = from.clone();
+                    frame.lerp(to, segment.easing.apply(i as f32 / segment.frames as f32));
+                    frame
+                })
+            })
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Segment {
+    pub frames: u32,
+    pub easing: Easing,
+}
+
+impl Default for Segment {
+    fn default() -> Self {
+        Segment {
+            frames: 360,
+            easing: Easing::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    #[default]
+    Cubic,
+    EaseIn,
+    EaseOut,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [Easing::Linear, Easing::Cubic, Easing::EaseIn, Easing::EaseOut];
+
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::Cubic => t * t * (3.0 - 2.0 * t),
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+        }
+    }
+}
+
+impl Display for Easing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Easing::Linear => "Linear",
+            Easing::Cubic => "Cubic",
+            Easing::EaseIn => "Ease in",
+            Easing::EaseOut => "Ease out",
+        };
+        write!(f, "{}", name)
+    }
+}
@@ -5,3 +5,10 @@ pub use sort_algos::*;
 
 mod animation;
 pub use animation::*;
+mod preset;
+pub use preset::*;
+mod sequence;
+pub use sequence::*;
+
+/// A single RGBA pixel, laid out the same way as the bytes of an `image::RgbaImage`.
+pub type Pixel = [u8; 4];
@@ -0,0 +1,108 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::sorter::{Animateable, AvailableLineAlgos, AvailableSortAlgos};
+
+/// A full sorter setup that can be written to and read from disk.
+///
+/// The file format is picked from the extension, `.ron` files are written as RON and
+/// everything else as JSON.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Preset {
+    pub line_algo: AvailableLineAlgos,
+    pub sort_algo: AvailableSortAlgos,
+}
+
+impl Preset {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PresetError> {
+        load_file(path.as_ref())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PresetError> {
+        save_file(self, path.as_ref())
+    }
+}
+
+impl Animateable for Preset {
+    //Keyframes with a different algorithm can't be blended, so they're held until the next one.
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        if self.line_algo == target.line_algo {
+            self.line_algo.lerp(&target.line_algo, weight);
+        }
+        if self.sort_algo == target.sort_algo {
+            self.sort_algo.lerp(&target.sort_algo, weight);
+        }
+    }
+}
+
+pub(crate) fn load_file<T: DeserializeOwned>(path: &Path) -> Result<T, PresetError> {
+    let contents = std::fs::read_to_string(path)?;
+    if is_ron(path) {
+        Ok(ron::from_str(&contents)?)
+    } else {
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+pub(crate) fn save_file<T: Serialize>(value: &T, path: &Path) -> Result<(), PresetError> {
+    let contents = if is_ron(path) {
+        ron::ser::to_string_pretty(value, Default::default())?
+    } else {
+        serde_json::to_string_pretty(value)?
+    };
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+fn is_ron(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "ron")
+}
+
+/// Errors from reading or writing presets and animation projects.
+#[derive(Debug)]
+pub enum PresetError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl Display for PresetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresetError::Io(error) => write!(f, "{}", error),
+            PresetError::Json(error) => write!(f, "invalid JSON: {}", error),
+            PresetError::Ron(error) => write!(f, "invalid RON: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<std::io::Error> for PresetError {
+    fn from(error: std::io::Error) -> Self {
+        PresetError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(error: serde_json::Error) -> Self {
+        PresetError::Json(error)
+    }
+}
+
+//Errors from writing have no position, and a zero one is left out when displayed.
+impl From<ron::Error> for PresetError {
+    fn from(error: ron::Error) -> Self {
+        let position = ron::error::Position { line: 0, col: 0 };
+        PresetError::Ron(ron::error::SpannedError { code: error, position })
+    }
+}
+
+impl From<ron::error::SpannedError> for PresetError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        PresetError::Ron(error)
+    }
+}
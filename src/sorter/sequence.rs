@@ -0,0 +1,139 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use rayon::prelude::*;
+
+/// The frames of a video as a folder of numbered images, kept in frame number order.
+pub struct FrameSequence {
+    /// File name of every frame, used again when writing the frames back out.
+    pub names: Vec<PathBuf>,
+    pub frames: Vec<RgbaImage>,
+}
+
+impl FrameSequence {
+    /// Reads every image in `dir`, sorted by file name with numbers compared by value, so
+    /// `frame2.png` comes before `frame10.png`. Files that aren't images are skipped.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, SequenceError> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.retain(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok());
+        paths.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+
+        let frames = paths
+            .par_iter()
+            .map(|path| Ok(image::open(path)?.to_rgba8()))
+            .collect::<Result<Vec<_>, SequenceError>>()?;
+
+        let Some(first) = frames.first() else {
+            return Err(SequenceError::Empty);
+        };
+        let size = first.dimensions();
+        if let Some(i) = frames.iter().position(|frame| frame.dimensions() != size) {
+            return Err(SequenceError::SizeMismatch(paths[i].clone()));
+        }
+
+        let names = paths
+            .iter()
+            .map(|path| PathBuf::from(path.file_name().unwrap_or_default()))
+            .collect();
+        Ok(FrameSequence { names, frames })
+    }
+
+    /// Writes every frame into `dir` under its original file name, creating `dir` if needed.
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<(), SequenceError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        self.names
+            .par_iter()
+            .zip(&self.frames)
+            .try_for_each(|(name, frame)| Ok(frame.save(dir.join(name))?))
+    }
+}
+
+/// Compares two names piece by piece, runs of digits by their value and everything else as text.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_rest, mut b_rest) = (a, b);
+    while let (Some(a_first), Some(b_first)) = (a_rest.chars().next(), b_rest.chars().next()) {
+        let order = if a_first.is_ascii_digit() && b_first.is_ascii_digit() {
+            let a_digits = digit_run(a_rest);
+            let b_digits = digit_run(b_rest);
+            a_rest = &a_rest[a_digits.len()..];
+            b_rest = &b_rest[b_digits.len()..];
+
+            let a_number = a_digits.trim_start_matches('0');
+            let b_number = b_digits.trim_start_matches('0');
+            a_number.len().cmp(&b_number.len()).then(a_number.cmp(b_number))
+        } else {
+            a_rest = &a_rest[a_first.len_utf8()..];
+            b_rest = &b_rest[b_first.len_utf8()..];
+            a_first.cmp(&b_first)
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+
+    //Names that only differ in leading zeros still need a consistent order.
+    a_rest.len().cmp(&b_rest.len()).then(a.cmp(b))
+}
+
+fn digit_run(text: &str) -> &str {
+    let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Errors from reading or writing frame sequences.
+#[derive(Debug)]
+pub enum SequenceError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    /// The folder didn't contain any images.
+    Empty,
+    /// This frame isn't the same size as the first one.
+    SizeMismatch(PathBuf),
+}
+
+impl Display for SequenceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceError::Io(error) => write!(f, "{}", error),
+            SequenceError::Image(error) => write!(f, "{}", error),
+            SequenceError::Empty => write!(f, "no images found"),
+            SequenceError::SizeMismatch(path) => {
+                write!(f, "{} isn't the same size as the first frame", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+impl From<std::io::Error> for SequenceError {
+    fn from(error: std::io::Error) -> Self {
+        SequenceError::Io(error)
+    }
+}
+
+impl From<image::ImageError> for SequenceError {
+    fn from(error: image::ImageError) -> Self {
+        SequenceError::Image(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_numbers_sort_by_value() {
+        let mut names = ["frame10.png", "frame2.png", "frame1.png", "frame002.png", "frame_a.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            ["frame1.png", "frame002.png", "frame2.png", "frame10.png", "frame_a.png"]
+        );
+    }
+}
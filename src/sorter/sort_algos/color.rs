@@ -0,0 +1,75 @@
+use std::sync::OnceLock;
+
+use crate::sorter::Pixel;
+
+/// Turns an 8 bit sRGB channel into linear light in `0.0..=1.0`.
+pub fn srgb_to_linear(channel: u8) -> f32 {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+
+    let table = TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let c = i as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    });
+    table[channel as usize]
+}
+
+/// The pixel's colour in linear light, ignoring alpha.
+pub fn linear_rgb(pixel: &Pixel) -> [f32; 3] {
+    [pixel[0], pixel[1], pixel[2]].map(srgb_to_linear)
+}
+
+/// CIE XYZ under a D65 white point, with white at `Y = 1.0`.
+pub fn xyz(pixel: &Pixel) -> [f32; 3] {
+    let [r, g, b] = linear_rgb(pixel);
+    [
+        0.4124564 * r + 0.3575761 * g + 0.1804375 * b,
+        0.2126729 * r + 0.7151522 * g + 0.0721750 * b,
+        0.0193339 * r + 0.119192 * g + 0.9503041 * b,
+    ]
+}
+
+/// CIELAB `[L*, a*, b*]`, where `L*` runs from 0 to 100 and `a*` and `b*` are roughly
+/// within `-128.0..=127.0` for sRGB colours.
+pub fn lab(pixel: &Pixel) -> [f32; 3] {
+    const WHITE: [f32; 3] = [0.95047, 1.0, 1.08883];
+    const DELTA: f32 = 6.0 / 29.0;
+
+    let f = |t: f32| {
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+
+    let [x, y, z] = xyz(pixel);
+    let (fx, fy, fz) = (f(x / WHITE[0]), f(y / WHITE[1]), f(z / WHITE[2]));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// OKLab `[L, a, b]`, where `L` runs from 0 to 1.
+pub fn oklab(pixel: &Pixel) -> [f32; 3] {
+    let [r, g, b] = linear_rgb(pixel);
+
+    let l = (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+    [
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+/// OKLCH `[L, C, h]`, OKLab in polar form with the hue `h` in degrees.
+pub fn oklch(pixel: &Pixel) -> [f32; 3] {
+    let [l, a, b] = oklab(pixel);
+    [l, a.hypot(b), b.atan2(a).to_degrees().rem_euclid(360.0)]
+}
@@ -1,9 +1,17 @@
+mod color;
+mod native_script;
+mod pixel_key;
+mod script;
 mod span_sort;
 
-use crate::sorter::Animateable;
-use eframe::epaint::Color32;
+use crate::sorter::{Animateable, Pixel};
+#[cfg(feature = "gui")]
 use egui::Ui;
+pub use color::*;
+pub use pixel_key::*;
+pub use script::*;
 pub use span_sort::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 
 //T is the type that represents a pixel
@@ -11,21 +19,34 @@ use std::fmt::{Debug, Formatter};
 pub trait SortMethod<P, R>: Sync + Clone {
     fn sort(&self, pixels: &mut [P]) -> R;
 
+    /// Gets a look at every pixel of an image before any of its lines are sorted, to work out
+    /// up front whatever only depends on the colours. Can be called more than once, and skipping
+    /// it must not change how anything is sorted.
+    fn prepare(&mut self, _pixels: &[P]) {}
+
+    #[cfg(feature = "gui")]
     fn ui(&mut self, ui: &mut Ui);
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AvailableSortAlgos {
     SpanSort(SpanSortMethod),
 }
 
-impl SortMethod<Color32, ()> for AvailableSortAlgos {
-    fn sort(&self, pixels: &mut [Color32]) -> () {
+impl SortMethod<Pixel, ()> for AvailableSortAlgos {
+    fn sort(&self, pixels: &mut [Pixel]) {
         match self {
             AvailableSortAlgos::SpanSort(sort_algo) => sort_algo.sort(pixels),
         }
     }
 
+    fn prepare(&mut self, pixels: &[Pixel]) {
+        match self {
+            AvailableSortAlgos::SpanSort(sort_algo) => sort_algo.prepare(pixels),
+        }
+    }
+
+    #[cfg(feature = "gui")]
     fn ui(&mut self, ui: &mut Ui) {
         match self {
             AvailableSortAlgos::SpanSort(sort_algo) => sort_algo.ui(ui),
@@ -33,15 +54,21 @@ impl SortMethod<Color32, ()> for AvailableSortAlgos {
     }
 }
 
+impl AvailableSortAlgos {
+    /// The first problem with a script in use, including errors hit while sorting.
+    pub fn script_error(&self) -> Option<String> {
+        match self {
+            AvailableSortAlgos::SpanSort(sort_algo) => sort_algo.config.script_error(),
+        }
+    }
+}
+
 impl Animateable for AvailableSortAlgos {
     fn lerp(&mut self, target: &Self, weight: f32) {
         match (self, target) {
             (AvailableSortAlgos::SpanSort(sort_algo), AvailableSortAlgos::SpanSort(target)) => {
                 sort_algo.lerp(target, weight);
             },
-            _ => {
-                eprintln!("Either Self and Target don't match, or the type you're trying to interpolate doesn't implement animateable!");
-            }
         }
     }
 }
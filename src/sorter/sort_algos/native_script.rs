@@ -0,0 +1,426 @@
+use rhai::Dynamic;
+
+use crate::sorter::sort_algos::script::Variable;
+use crate::sorter::Pixel;
+
+/// A [`PixelScript`](super::PixelScript) turned into plain Rust, which runs a few hundred times
+/// faster than rhai does.
+///
+/// Only the arithmetic, comparisons, logic, `if`s and maths functions most scripts are made of are
+/// understood, anything else doesn't parse and is left to rhai. What does parse gives exactly
+/// what rhai would: the same precedence, the same mixing of integers into floats and the same fuzzy
+/// float comparisons.
+pub(crate) struct NativeScript(Expr);
+
+impl NativeScript {
+    /// Parses `source`, which has already been checked to compile with rhai, with `variables`
+    /// being the names the script can use for parts of the pixel.
+    pub fn parse(source: &str, variables: &[(&str, Variable)]) -> Option<Self> {
+        let mut parser = Parser {
+            tokens: tokens(source)?,
+            next: 0,
+            variables,
+        };
+        let expr = parser.expr(0)?;
+        (parser.next == parser.tokens.len()).then_some(NativeScript(expr))
+    }
+
+    pub fn eval(&self, pixel: &Pixel) -> Dynamic {
+        match &self.0 {
+            Expr::Float(number) => Dynamic::from_float(number.eval(pixel)),
+            Expr::Int(number) => Dynamic::from_int(number.eval(pixel) as i64),
+            Expr::Bool(condition) => Dynamic::from_bool(condition.eval(pixel)),
+        }
+    }
+}
+
+/// A parsed expression, by what it gives back.
+enum Expr {
+    Float(Value<f64>),
+    /// Integers only come from literals, so they're worked out as floats without losing anything.
+    /// Maths on two of them is left to rhai, which does it in integers.
+    Int(Value<f64>),
+    Bool(Value<bool>),
+}
+
+/// Part of a script, either known up front or worked out from the pixel.
+///
+/// Every operator builds its own closure around the closures of its operands, so running a script
+/// is a handful of calls rather than a walk over a tree.
+enum Value<T> {
+    Constant(T),
+    Function(Box<dyn Fn(&Pixel) -> T + Send + Sync>),
+}
+
+impl<T: Copy + Send + Sync + 'static> Value<T> {
+    fn eval(&self, pixel: &Pixel) -> T {
+        match self {
+            Value::Constant(value) => *value,
+            Value::Function(function) => function(pixel),
+        }
+    }
+
+    fn into_function(self) -> Box<dyn Fn(&Pixel) -> T + Send + Sync> {
+        match self {
+            Value::Constant(value) => Box::new(move |_| value),
+            Value::Function(function) => function,
+        }
+    }
+}
+
+fn map<T, U>(x: Value<T>, f: impl Fn(T) -> U + Send + Sync + 'static) -> Value<U>
+where
+    T: Copy + Send + Sync + 'static,
+{
+    match x {
+        Value::Constant(x) => Value::Constant(f(x)),
+        Value::Function(x) => Value::Function(Box::new(move |pixel| f(x(pixel)))),
+    }
+}
+
+/// Applies `f` to two values, keeping constants out of the closure it builds.
+fn combine<T, U, F>(x: Value<T>, y: Value<T>, f: F) -> Value<U>
+where
+    T: Copy + Send + Sync + 'static,
+    F: Fn(T, T) -> U + Send + Sync + 'static,
+{
+    match (x, y) {
+        (Value::Constant(x), Value::Constant(y)) => Value::Constant(f(x, y)),
+        (Value::Constant(x), y) => {
+            let y = y.into_function();
+            Value::Function(Box::new(move |pixel| f(x, y(pixel))))
+        }
+        (x, Value::Constant(y)) => {
+            let x = x.into_function();
+            Value::Function(Box::new(move |pixel| f(x(pixel), y)))
+        }
+        (x, y) => {
+            let (x, y) = (x.into_function(), y.into_function());
+            Value::Function(Box::new(move |pixel| f(x(pixel), y(pixel))))
+        }
+    }
+}
+
+fn if_else<T>(condition: Value<bool>, then: Value<T>, otherwise: Value<T>) -> Value<T>
+where
+    T: Copy + Send + Sync + 'static,
+{
+    let (condition, then, otherwise) =
+        (condition.into_function(), then.into_function(), otherwise.into_function());
+    Value::Function(Box::new(move |pixel| {
+        if condition(pixel) {
+            then(pixel)
+        } else {
+            otherwise(pixel)
+        }
+    }))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Int(i64),
+    Float(f64),
+    Name(&'a str),
+    Symbol(&'static str),
+}
+
+/// Longer symbols come first, so `<=` isn't read as `<` followed by `=`.
+const SYMBOLS: [&str; 20] = [
+    "**", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "(", ")",
+    "{", "}", ",",
+];
+
+/// Splits `source` into tokens, giving up on anything rhai has but [`Parser`] doesn't, like
+/// exponents in numbers, method calls or comments.
+fn tokens(source: &str) -> Option<Vec<Token<'_>>> {
+    let digits = |text: &str| text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+
+    let mut tokens = vec![];
+    let mut rest = source.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let length = if first.is_ascii_digit() {
+            let mut length = digits(rest);
+            if let Some(fraction) = rest[length..].strip_prefix('.') {
+                if fraction.starts_with(|c: char| c.is_ascii_digit()) {
+                    length += 1 + digits(fraction);
+                }
+            }
+            if rest[length..].starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.') {
+                return None;
+            }
+
+            let literal = &rest[..length];
+            if literal.contains('.') {
+                tokens.push(Token::Float(literal.parse().ok()?));
+            } else {
+                //Bigger integers don't fit in a float exactly.
+                let int = literal.parse::<i64>().ok().filter(|int| *int <= 1 << 53)?;
+                tokens.push(Token::Int(int));
+            }
+            length
+        } else if first.is_ascii_alphabetic() || first == '_' {
+            let length = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(&rest[..length]));
+            length
+        } else {
+            let symbol = SYMBOLS.into_iter().find(|symbol| rest.starts_with(symbol))?;
+            tokens.push(Token::Symbol(symbol));
+            symbol.len()
+        };
+        rest = rest[length..].trim_start();
+    }
+    Some(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    next: usize,
+    variables: &'a [(&'a str, Variable)],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn take(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.next += 1;
+        token
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Option<()> {
+        (self.take()? == Token::Symbol(symbol)).then_some(())
+    }
+
+    /// Parses binary operators binding at least as tightly as `min_precedence`, using rhai's
+    /// precedences.
+    fn expr(&mut self, min_precedence: u8) -> Option<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Symbol(op)) = self.peek() {
+            let precedence = match op {
+                "||" => 30,
+                "&&" => 60,
+                "==" | "!=" => 90,
+                "<" | "<=" | ">" | ">=" => 130,
+                "+" | "-" => 150,
+                "*" | "/" | "%" => 180,
+                "**" => 190,
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.next += 1;
+            //Only `**` binds to the right.
+            let rhs = self.expr(if op == "**" { precedence } else { precedence + 1 })?;
+            lhs = binary(op, lhs, rhs)?;
+        }
+        Some(lhs)
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        match self.peek()? {
+            Token::Symbol("-") => {
+                self.next += 1;
+                match self.unary()? {
+                    Expr::Float(x) => Some(Expr::Float(map(x, |x: f64| -x))),
+                    Expr::Int(x) => Some(Expr::Int(map(x, |x: f64| -x))),
+                    Expr::Bool(_) => None,
+                }
+            }
+            Token::Symbol("+") => {
+                self.next += 1;
+                self.unary().filter(|x| !matches!(x, Expr::Bool(_)))
+            }
+            Token::Symbol("!") => {
+                self.next += 1;
+                match self.unary()? {
+                    Expr::Bool(x) => Some(Expr::Bool(map(x, |x: bool| !x))),
+                    _ => None,
+                }
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Option<Expr> {
+        match self.take()? {
+            Token::Int(int) => Some(Expr::Int(Value::Constant(int as f64))),
+            Token::Float(float) => Some(Expr::Float(Value::Constant(float))),
+            Token::Symbol("(") => {
+                let expr = self.expr(0)?;
+                self.expect(")")?;
+                Some(expr)
+            }
+            Token::Name("true") => Some(Expr::Bool(Value::Constant(true))),
+            Token::Name("false") => Some(Expr::Bool(Value::Constant(false))),
+            Token::Name("if") => self.if_expr(),
+            Token::Name(name) if self.peek() == Some(Token::Symbol("(")) => {
+                self.next += 1;
+                let mut args = vec![];
+                if self.peek() == Some(Token::Symbol(")")) {
+                    self.next += 1;
+                } else {
+                    loop {
+                        args.push(self.expr(0)?);
+                        match self.take()? {
+                            Token::Symbol(")") => break,
+                            Token::Symbol(",") => continue,
+                            _ => return None,
+                        }
+                    }
+                }
+                call(name, args)
+            }
+            Token::Name(name) => {
+                let (_, variable) = self.variables.iter().find(|(variable, _)| *variable == name)?;
+                Some(Expr::Float(Value::Function(Box::new(*variable))))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses the rest of an `if`, which needs an `else` here so it always gives back a value.
+    fn if_expr(&mut self) -> Option<Expr> {
+        let Expr::Bool(condition) = self.expr(0)? else {
+            return None;
+        };
+        let then = self.block()?;
+        if self.take()? != Token::Name("else") {
+            return None;
+        }
+        let otherwise = if self.peek() == Some(Token::Name("if")) {
+            self.next += 1;
+            self.if_expr()?
+        } else {
+            self.block()?
+        };
+
+        match (then, otherwise) {
+            (Expr::Float(then), Expr::Float(otherwise)) => {
+                Some(Expr::Float(if_else(condition, then, otherwise)))
+            }
+            (Expr::Int(then), Expr::Int(otherwise)) => {
+                Some(Expr::Int(if_else(condition, then, otherwise)))
+            }
+            (Expr::Bool(then), Expr::Bool(otherwise)) => {
+                Some(Expr::Bool(if_else(condition, then, otherwise)))
+            }
+            _ => None,
+        }
+    }
+
+    fn block(&mut self) -> Option<Expr> {
+        self.expect("{")?;
+        let expr = self.expr(0)?;
+        self.expect("}")?;
+        Some(expr)
+    }
+}
+
+fn binary(op: &str, lhs: Expr, rhs: Expr) -> Option<Expr> {
+    let (x, y) = match (lhs, rhs) {
+        (Expr::Bool(x), Expr::Bool(y)) => {
+            return match op {
+                "&&" => {
+                    let (x, y) = (x.into_function(), y.into_function());
+                    Some(Expr::Bool(Value::Function(Box::new(move |pixel| x(pixel) && y(pixel)))))
+                }
+                "||" => {
+                    let (x, y) = (x.into_function(), y.into_function());
+                    Some(Expr::Bool(Value::Function(Box::new(move |pixel| x(pixel) || y(pixel)))))
+                }
+                "==" => Some(Expr::Bool(combine(x, y, |x, y| x == y))),
+                "!=" => Some(Expr::Bool(combine(x, y, |x, y| x != y))),
+                _ => None,
+            };
+        }
+        (Expr::Bool(_), _) | (_, Expr::Bool(_)) | (Expr::Int(_), Expr::Int(_)) => return None,
+        (Expr::Float(x) | Expr::Int(x), Expr::Float(y) | Expr::Int(y)) => (x, y),
+    };
+
+    let expr = match op {
+        "+" => Expr::Float(combine(x, y, |x, y| x + y)),
+        "-" => Expr::Float(combine(x, y, |x, y| x - y)),
+        "*" => Expr::Float(combine(x, y, |x, y| x * y)),
+        "/" => Expr::Float(combine(x, y, |x, y| x / y)),
+        "%" => Expr::Float(combine(x, y, |x, y| x % y)),
+        "**" => Expr::Float(combine(x, y, f64::powf)),
+        "==" => Expr::Bool(combine(x, y, |x, y| {
+            let max = scale(x, y);
+            max == 0.0 || (x - y).abs() / max <= f64::EPSILON
+        })),
+        "!=" => Expr::Bool(combine(x, y, |x, y| {
+            let max = scale(x, y);
+            max != 0.0 && (x - y).abs() / max > f64::EPSILON
+        })),
+        ">" => Expr::Bool(combine(x, y, |x, y| {
+            let max = scale(x, y);
+            max != 0.0 && (x - y) / max > f64::EPSILON
+        })),
+        ">=" => Expr::Bool(combine(x, y, |x, y| {
+            let max = scale(x, y);
+            max == 0.0 || (x - y) / max > -f64::EPSILON
+        })),
+        "<" => Expr::Bool(combine(x, y, |x, y| {
+            let max = scale(x, y);
+            max != 0.0 && (y - x) / max > f64::EPSILON
+        })),
+        "<=" => Expr::Bool(combine(x, y, |x, y| {
+            let max = scale(x, y);
+            max == 0.0 || (y - x) / max > -f64::EPSILON
+        })),
+        _ => return None,
+    };
+    Some(expr)
+}
+
+/// rhai compares floats relative to this, so numbers only a rounding error apart count as equal.
+fn scale(x: f64, y: f64) -> f64 {
+    if x * y == 0.0 {
+        1.0
+    } else {
+        x.abs().max(y.abs())
+    }
+}
+
+/// The maths functions rhai has for floats.
+fn call(name: &str, args: Vec<Expr>) -> Option<Expr> {
+    let mut args = args.into_iter().map(|arg| match arg {
+        Expr::Float(x) => Some(x),
+        _ => None,
+    });
+    let number = match (args.next(), args.next(), args.next()) {
+        (Some(x), None, None) => {
+            let x = x?;
+            match name {
+                "abs" => map(x, f64::abs),
+                "sqrt" => map(x, f64::sqrt),
+                "exp" => map(x, f64::exp),
+                "ln" => map(x, f64::ln),
+                "log" => map(x, f64::log10),
+                "floor" => map(x, f64::floor),
+                "ceiling" => map(x, f64::ceil),
+                "round" => map(x, f64::round),
+                "sin" => map(x, f64::sin),
+                "cos" => map(x, f64::cos),
+                "tan" => map(x, f64::tan),
+                _ => return None,
+            }
+        }
+        (Some(x), Some(y), None) => {
+            let (x, y) = (x?, y?);
+            match name {
+                "min" => combine(x, y, |x, y| if x <= y { x } else { y }),
+                "max" => combine(x, y, |x, y| if x >= y { x } else { y }),
+                "log" => combine(x, y, f64::log),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(Expr::Float(number))
+}
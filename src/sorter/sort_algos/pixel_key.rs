@@ -0,0 +1,239 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::sorter::sort_algos::color::{lab, oklab, oklch};
+use crate::sorter::Pixel;
+
+/// A value that can be pulled out of a pixel, used both for checking thresholds and for sorting.
+///
+/// Every key is normalized to `0.0..=1.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PixelKey {
+    #[default]
+    Average,
+    Luminosity,
+    Red,
+    Green,
+    Blue,
+    Hue,
+    Saturation,
+    Lightness,
+    Value,
+    Chroma,
+    /// CIELAB perceptual lightness.
+    LabLightness,
+    /// CIELAB green to red axis.
+    LabA,
+    /// CIELAB blue to yellow axis.
+    LabB,
+    OklabLightness,
+    OklchChroma,
+    OklchHue,
+    /// A user written expression, see [`PixelScript`](crate::sorter::PixelScript). It has no
+    /// value of its own and is evaluated by [`SpanSortConfig`](crate::sorter::SpanSortConfig).
+    Script,
+}
+
+impl PixelKey {
+    pub const ALL: [PixelKey; 17] = [
+        PixelKey::Average,
+        PixelKey::Luminosity,
+        PixelKey::Red,
+        PixelKey::Green,
+        PixelKey::Blue,
+        PixelKey::Hue,
+        PixelKey::Saturation,
+        PixelKey::Lightness,
+        PixelKey::Value,
+        PixelKey::Chroma,
+        PixelKey::LabLightness,
+        PixelKey::LabA,
+        PixelKey::LabB,
+        PixelKey::OklabLightness,
+        PixelKey::OklchChroma,
+        PixelKey::OklchHue,
+        PixelKey::Script,
+    ];
+
+    pub fn key(&self, pixel: &Pixel) -> f32 {
+        self.key_with_hue_origin(pixel, 0.0)
+    }
+
+    /// Like [`PixelKey::key`], but hue is measured from `hue_origin` degrees instead of from red,
+    /// which moves where the hue circle is cut open.
+    pub fn key_with_hue_origin(&self, pixel: &Pixel, hue_origin: f32) -> f32 {
+        match self {
+            PixelKey::Average => average(pixel),
+            PixelKey::Luminosity => luminosity(pixel),
+            PixelKey::Red => red(pixel),
+            PixelKey::Green => green(pixel),
+            PixelKey::Blue => blue(pixel),
+            PixelKey::Hue => hue_from(pixel, hue_origin),
+            PixelKey::Saturation => saturation(pixel),
+            PixelKey::Lightness => lightness(pixel),
+            PixelKey::Value => value(pixel),
+            PixelKey::Chroma => chroma(pixel),
+            PixelKey::LabLightness => lab_lightness(pixel),
+            PixelKey::LabA => lab_a(pixel),
+            PixelKey::LabB => lab_b(pixel),
+            PixelKey::OklabLightness => oklab_lightness(pixel),
+            PixelKey::OklchChroma => oklch_chroma(pixel),
+            PixelKey::OklchHue => oklch_hue_from(pixel, hue_origin),
+            PixelKey::Script => 0.0,
+        }
+    }
+}
+
+impl Display for PixelKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PixelKey::Average => "Average",
+            PixelKey::Luminosity => "Luminosity",
+            PixelKey::Red => "Red",
+            PixelKey::Green => "Green",
+            PixelKey::Blue => "Blue",
+            PixelKey::Hue => "Hue",
+            PixelKey::Saturation => "Saturation",
+            PixelKey::Lightness => "Lightness",
+            PixelKey::Value => "Value",
+            PixelKey::Chroma => "Chroma",
+            PixelKey::LabLightness => "L* (CIELAB)",
+            PixelKey::LabA => "a* (CIELAB)",
+            PixelKey::LabB => "b* (CIELAB)",
+            PixelKey::OklabLightness => "Lightness (OKLab)",
+            PixelKey::OklchChroma => "Chroma (OKLCH)",
+            PixelKey::OklchHue => "Hue (OKLCH)",
+            PixelKey::Script => "Script",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Any channel scaled from `0..=255` to `0.0..=1.0`.
+fn normalize(channel: u8) -> f32 {
+    channel as f32 / 255.0
+}
+
+pub fn average(pixel: &Pixel) -> f32 {
+    let [r, g, b, _] = *pixel;
+
+    (r as u16 + g as u16 + b as u16) as f32 / (3.0 * 255.0)
+}
+
+pub fn luminosity(pixel: &Pixel) -> f32 {
+    let [r, g, b, _] = *pixel;
+
+    let luminosity = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+
+    luminosity / 255.0
+}
+
+pub fn red(pixel: &Pixel) -> f32 {
+    normalize(pixel[0])
+}
+
+pub fn green(pixel: &Pixel) -> f32 {
+    normalize(pixel[1])
+}
+
+pub fn blue(pixel: &Pixel) -> f32 {
+    normalize(pixel[2])
+}
+
+/// Hue as a share of the full circle, with red at 0. Greys have no hue and get 0.
+pub fn hue(pixel: &Pixel) -> f32 {
+    hue_from(pixel, 0.0)
+}
+
+fn hue_to_key(degrees: f32, origin: f32) -> f32 {
+    (degrees - origin).rem_euclid(360.0) / 360.0
+}
+
+fn hue_from(pixel: &Pixel, origin: f32) -> f32 {
+    let [r, g, b, _] = pixel.map(|channel| channel as f32);
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    if chroma == 0.0 {
+        return 0.0;
+    }
+
+    let sector = if max == r {
+        (g - b) / chroma
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+
+    hue_to_key(sector * 60.0, origin)
+}
+
+/// HSV saturation, how much of the brightest channel is colour rather than grey.
+pub fn saturation(pixel: &Pixel) -> f32 {
+    let max = value(pixel);
+    if max == 0.0 {
+        return 0.0;
+    }
+
+    chroma(pixel) / max
+}
+
+/// HSL lightness, halfway between the brightest and darkest channel.
+pub fn lightness(pixel: &Pixel) -> f32 {
+    let [r, g, b, _] = *pixel;
+    (r.max(g).max(b) as u16 + r.min(g).min(b) as u16) as f32 / (2.0 * 255.0)
+}
+
+/// HSV value, the brightest channel.
+pub fn value(pixel: &Pixel) -> f32 {
+    let [r, g, b, _] = *pixel;
+    normalize(r.max(g).max(b))
+}
+
+/// Difference between the brightest and darkest channel.
+pub fn chroma(pixel: &Pixel) -> f32 {
+    let [r, g, b, _] = *pixel;
+    normalize(r.max(g).max(b) - r.min(g).min(b))
+}
+
+/// CIELAB `L*` scaled from `0..=100` to `0.0..=1.0`.
+pub fn lab_lightness(pixel: &Pixel) -> f32 {
+    (lab(pixel)[0] / 100.0).clamp(0.0, 1.0)
+}
+
+/// CIELAB `a*` scaled from `-128.0..=127.0` to `0.0..=1.0`, so neutral greys sit near the middle.
+pub fn lab_a(pixel: &Pixel) -> f32 {
+    ((lab(pixel)[1] + 128.0) / 255.0).clamp(0.0, 1.0)
+}
+
+/// CIELAB `b*` scaled from `-128.0..=127.0` to `0.0..=1.0`, so neutral greys sit near the middle.
+pub fn lab_b(pixel: &Pixel) -> f32 {
+    ((lab(pixel)[2] + 128.0) / 255.0).clamp(0.0, 1.0)
+}
+
+/// OKLab `L`, already in `0.0..=1.0`.
+pub fn oklab_lightness(pixel: &Pixel) -> f32 {
+    oklab(pixel)[0].clamp(0.0, 1.0)
+}
+
+/// OKLCH chroma, scaled so the most saturated sRGB colours land near 1.
+pub fn oklch_chroma(pixel: &Pixel) -> f32 {
+    const MAX_SRGB_CHROMA: f32 = 0.33;
+
+    (oklch(pixel)[1] / MAX_SRGB_CHROMA).clamp(0.0, 1.0)
+}
+
+/// OKLCH hue as a share of the full circle. Greys have no hue and get 0.
+pub fn oklch_hue(pixel: &Pixel) -> f32 {
+    oklch_hue_from(pixel, 0.0)
+}
+
+fn oklch_hue_from(pixel: &Pixel, origin: f32) -> f32 {
+    let [_, chroma, hue] = oklch(pixel);
+    if chroma < 1e-4 {
+        return 0.0;
+    }
+
+    hue_to_key(hue, origin)
+}
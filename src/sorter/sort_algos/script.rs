@@ -0,0 +1,294 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
+use std::hash::{BuildHasherDefault, Hasher};
+use std::sync::{Arc, OnceLock};
+
+use rayon::prelude::*;
+use rhai::{Dynamic, Engine, OptimizationLevel, Scope, AST};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::sorter::sort_algos::native_script::NativeScript;
+use crate::sorter::sort_algos::pixel_key::{hue, lightness, saturation, value};
+use crate::sorter::Pixel;
+
+/// A rhai expression evaluated once per pixel.
+///
+/// The expression sees the pixel as `r`, `g`, `b` and `a`, plus `h` for hue in degrees and
+/// `s`, `l` and `v` for HSV saturation, HSL lightness and HSV value. Everything except `h`
+/// runs from 0 to 255.
+///
+/// The source is only compiled the first time it's needed, and clones share the compiled
+/// form, so copies handed to other threads don't compile it again. Most scripts are simple enough
+/// to run as plain Rust instead of through rhai. The rest only see the pixel, so
+/// [`PixelScript::prepare`] runs them once per distinct colour of an image, in parallel, and
+/// sorting only has to look the results up.
+#[derive(Clone)]
+pub struct PixelScript {
+    source: String,
+    compiled: Arc<OnceLock<Result<Compiled, String>>>,
+    /// The first error the script ran into while sorting, shared with clones so the copy shown
+    /// in the ui hears about errors from the copy doing the sorting.
+    runtime_error: Arc<OnceLock<String>>,
+    /// Results for the colours passed to [`PixelScript::prepare`], keyed by the packed colour.
+    prepared: Arc<ColourMap<Dynamic>>,
+}
+
+impl PixelScript {
+    pub fn new(source: impl Into<String>) -> Self {
+        PixelScript {
+            source: source.into(),
+            compiled: Arc::new(OnceLock::new()),
+            runtime_error: Arc::new(OnceLock::new()),
+            prepared: Arc::default(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn compiled(&self) -> Result<&Compiled, &str> {
+        self.compiled
+            .get_or_init(|| {
+                let ast = engine()
+                    .compile_expression(&self.source)
+                    .map_err(|error| error.to_string())?;
+                let native = NativeScript::parse(&self.source, &VARIABLES);
+                Ok(Compiled { ast, native })
+            })
+            .as_ref()
+            .map_err(|error| error.as_str())
+    }
+
+    /// Runs the script on every colour in `pixels` it hasn't seen yet, so later calls only have
+    /// to look the results up.
+    ///
+    /// Errors are recorded like they are while sorting, and the colour they happened on is
+    /// remembered as giving `()`.
+    pub fn prepare(&mut self, pixels: &[Pixel]) {
+        //Scripts that run natively are cheaper to run than to look up.
+        let Ok(Compiled { ast, native: None }) = self.compiled() else {
+            return;
+        };
+
+        let colours = pixels
+            .par_iter()
+            .map(|pixel| u32::from_le_bytes(*pixel))
+            .filter(|colour| !self.prepared.contains_key(colour))
+            .fold(ColourSet::default, |mut colours, colour| {
+                colours.insert(colour);
+                colours
+            })
+            .reduce(ColourSet::default, |mut colours, other| {
+                colours.extend(other);
+                colours
+            });
+
+        let results = colours
+            .into_par_iter()
+            .map(|colour| {
+                let pixel = colour.to_le_bytes();
+                let result = eval_rhai(ast, &pixel);
+                let result = self.record_error(result, &pixel).unwrap_or(Dynamic::UNIT);
+                (colour, result)
+            })
+            .collect::<Vec<_>>();
+        Arc::make_mut(&mut self.prepared).extend(results);
+    }
+
+    fn eval(&self, pixel: &Pixel) -> Result<Dynamic, String> {
+        let compiled = self.compiled()?;
+        if let Some(native) = &compiled.native {
+            return Ok(native.eval(pixel));
+        }
+
+        match self.prepared.get(&u32::from_le_bytes(*pixel)) {
+            Some(result) => Ok(result.clone()),
+            None => eval_rhai(&compiled.ast, pixel),
+        }
+    }
+
+    /// Runs the script as a sort key, which has to give back a number.
+    pub fn key(&self, pixel: &Pixel) -> Result<f32, String> {
+        let key = self.eval(pixel).and_then(|result| {
+            result
+                .as_float()
+                .or_else(|_| result.as_int().map(|key| key as f64))
+                .map(|key| key as f32)
+                .map_err(|type_name| format!("Expected a number, got {}", type_name))
+        });
+        self.record_error(key, pixel)
+    }
+
+    /// Runs the script as a threshold, which has to give back `true` or `false`.
+    pub fn predicate(&self, pixel: &Pixel) -> Result<bool, String> {
+        let predicate = self.eval(pixel).and_then(|result| {
+            result
+                .as_bool()
+                .map_err(|type_name| format!("Expected true or false, got {}", type_name))
+        });
+        self.record_error(predicate, pixel)
+    }
+
+    fn record_error<T>(&self, result: Result<T, String>, pixel: &Pixel) -> Result<T, String> {
+        if let Err(ref error) = result {
+            self.runtime_error
+                .get_or_init(|| format!("{} (on pixel {:?})", error, pixel));
+        }
+        result
+    }
+
+    /// The first problem with the script, if any: a compile error, an error or wrong result
+    /// type when run on a grey pixel, or the first error it ran into while sorting.
+    pub fn check(&self, predicate: bool) -> Option<String> {
+        const GREY: Pixel = [128, 128, 128, 255];
+        let error = if predicate {
+            self.predicate(&GREY).err()
+        } else {
+            self.key(&GREY).err()
+        };
+        error.or_else(|| self.runtime_error.get().cloned())
+    }
+}
+
+/// The names scripts see the pixel under, with how each is worked out from it.
+const VARIABLES: [(&str, Variable); 8] = [
+    ("r", |pixel| pixel[0] as f64),
+    ("g", |pixel| pixel[1] as f64),
+    ("b", |pixel| pixel[2] as f64),
+    ("a", |pixel| pixel[3] as f64),
+    ("h", |pixel| hue(pixel) as f64 * 360.0),
+    ("s", |pixel| saturation(pixel) as f64 * 255.0),
+    ("l", |pixel| lightness(pixel) as f64 * 255.0),
+    ("v", |pixel| value(pixel) as f64 * 255.0),
+];
+
+pub(crate) type Variable = fn(&Pixel) -> f64;
+
+/// A script compiled by rhai, and also to plain Rust if it's simple enough.
+struct Compiled {
+    ast: AST,
+    native: Option<NativeScript>,
+}
+
+fn eval_rhai(ast: &AST, pixel: &Pixel) -> Result<Dynamic, String> {
+    SCOPE.with(|scope| {
+        let mut scope = scope.borrow_mut();
+        if scope.len() != VARIABLES.len() {
+            scope.clear();
+            for (name, _) in VARIABLES {
+                scope.push(name, 0.0_f64);
+            }
+        }
+        for (name, variable) in VARIABLES {
+            if let Some(value) = scope.get_mut(name) {
+                *value = Dynamic::from_float(variable(pixel));
+            }
+        }
+
+        let result = engine().eval_ast_with_scope(&mut scope, ast);
+        scope.rewind(VARIABLES.len());
+        result.map_err(|error| error.to_string())
+    })
+}
+
+thread_local! {
+    /// Setting up a scope costs more than running most scripts, so each thread keeps one around
+    /// and only overwrites the values in it.
+    static SCOPE: RefCell<Scope<'static>> = RefCell::new(Scope::new());
+}
+
+type ColourMap<T> = HashMap<u32, T, BuildHasherDefault<ColourHasher>>;
+type ColourSet = HashSet<u32, BuildHasherDefault<ColourHasher>>;
+
+/// Hashes colours packed into a `u32` with a single multiply, a lot cheaper than the default
+/// hasher when every pixel of an image gets looked up.
+#[derive(Default)]
+struct ColourHasher(u64);
+
+impl Hasher for ColourHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u32(byte as u32);
+        }
+    }
+
+    fn write_u32(&mut self, colour: u32) {
+        let hash = (colour as u64 ^ self.0).wrapping_mul(0x9E3779B97F4A7C15);
+        //The low bits pick the bucket, fold the well mixed high bits into them.
+        self.0 = hash ^ hash >> 32;
+    }
+}
+
+/// The engine every script runs on, shared between threads.
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine.set_optimization_level(OptimizationLevel::Full);
+        engine
+    })
+}
+
+impl Debug for PixelScript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.source)
+    }
+}
+
+impl Serialize for PixelScript {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.source.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PixelScript {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(PixelScript::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_scripts_match_rhai() {
+        let sources = [
+            "0.3 * r + 0.6 * g + 0.1 * b",
+            "l > 60 && l < 200",
+            "!(s >= 100) || h <= 180.0",
+            "r == g != (b != 0)",
+            "-r ** 2 ** 0.5 - -g % 7",
+            "(r - g) / (b - a)",
+            "if h > 90 { 1 } else if s < 20 { -1 } else { 0 }",
+            "1 + if v > 128 { r } else { g } * 2",
+            "max(r, g) - min(b, abs(h - 180.0)) + sqrt(l) + log(v + 1.0, 2.0) + round(s / 3.0)",
+            "5",
+        ];
+        let pixels = [[0, 0, 0, 255], [255, 255, 255, 0], [200, 100, 50, 255], [7, 7, 8, 128]];
+
+        for source in sources {
+            let script = PixelScript::new(source);
+            let compiled = script.compiled().unwrap();
+            let native = compiled.native.as_ref().expect(source);
+            for pixel in pixels {
+                let expected = eval_rhai(&compiled.ast, &pixel).unwrap();
+                let actual = native.eval(&pixel);
+                assert_eq!(format!("{actual:?}"), format!("{expected:?}"), "{source} on {pixel:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_scripts_are_left_to_rhai() {
+        for source in ["r.abs()", "1e3 * r", "2 * 3 * r", "if l > 1.0 { r }", "r < 3.0 < 4.0"] {
+            assert!(PixelScript::new(source).compiled().unwrap().native.is_none(), "{source}");
+        }
+    }
+}
@@ -1,191 +1,306 @@
-use std::ops::Range;
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
 
-use egui::{Color32, ComboBox, Slider, Ui};
+#[cfg(feature = "gui")]
+use egui::{Color32, ComboBox, Slider, TextEdit, Ui};
 use rayon::prelude::*;
 
-use crate::sorter::Animateable;
+use serde::{Deserialize, Serialize};
+
+use crate::sorter::{Animateable, Pixel, PixelKey, PixelScript};
 use crate::sorter::sort_algos::SortMethod;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct SpanSortMethod {
     pub config: SpanSortConfig,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SpanSortConfig {
-    pub(crate) threshold: Range<u8>,
-    pub threshold_method: fn(&Color32) -> u8,
-    threshold_method_name: String,
-    pub(crate) invert_threshold: bool,
-
-    pub sorting_method: fn(&Color32) -> u8,
-    sorting_method_name: String,
-
+    /// Pixels whose threshold key falls within this range are sorted.
+    pub threshold: RangeInclusive<f32>,
+    pub threshold_method: PixelKey,
+    pub invert_threshold: bool,
+
+    pub sorting_method: PixelKey,
+    /// Sort `sorting_method` from high to low.
+    #[serde(default)]
+    pub descending: bool,
+    /// Keys that order the pixels `sorting_method` sees as equal, checked in order.
+    #[serde(default)]
+    pub tie_breakers: Vec<SortKey>,
+    /// Keep pixels with equal keys in their original order, so animations don't flicker.
+    #[serde(default)]
+    pub stable: bool,
+    /// Where the hue keys start counting from, in degrees. Hue wraps around, so this picks which
+    /// colour ends up at both ends of a hue sort.
+    #[serde(default)]
+    pub hue_origin: f32,
+    /// Decides which pixels are sorted when `threshold_method` is [`PixelKey::Script`], in place
+    /// of the threshold range.
+    #[serde(default = "default_threshold_script")]
+    pub threshold_script: PixelScript,
+    /// The key used wherever [`PixelKey::Script`] is picked as a sort key.
+    #[serde(default = "default_sort_script")]
+    pub sort_script: PixelScript,
+
+    #[cfg(feature = "gui")]
+    #[serde(skip, default = "rand::random")]
     id: u32,
 }
 
 impl Default for SpanSortConfig {
     fn default() -> Self {
         SpanSortConfig {
-            threshold: 0..255,
-            threshold_method: average,
-            threshold_method_name: "Average".to_string(),
+            threshold: 0.0..=1.0,
+            threshold_method: PixelKey::Average,
             invert_threshold: false,
 
-            sorting_method: average,
-            sorting_method_name: "Average".to_string(),
+            sorting_method: PixelKey::Average,
+            descending: false,
+            tie_breakers: vec![],
+            stable: false,
+            hue_origin: 0.0,
+            threshold_script: default_threshold_script(),
+            sort_script: default_sort_script(),
+            #[cfg(feature = "gui")]
             id: rand::random(),
         }
     }
 }
 
-impl SortMethod<Color32, ()> for SpanSortMethod {
-    fn sort(&self, pixels: &mut [Color32]) {
+fn default_threshold_script() -> PixelScript {
+    PixelScript::new("l > 60 && l < 200")
+}
+
+fn default_sort_script() -> PixelScript {
+    PixelScript::new("0.3 * r + 0.6 * g + 0.1 * b")
+}
+
+/// One key in the list a span is sorted by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub key: PixelKey,
+    pub descending: bool,
+}
+
+impl SpanSortConfig {
+    pub fn threshold_key(&self, pixel: &Pixel) -> f32 {
+        self.threshold_method.key_with_hue_origin(pixel, self.hue_origin)
+    }
+
+    /// The value of `key` for `pixel`, running `sort_script` for [`PixelKey::Script`].
+    ///
+    /// A script that fails gives 0 rather than stopping the sort, the error is kept on the script
+    /// so it can be shown.
+    pub fn key(&self, key: PixelKey, pixel: &Pixel) -> f32 {
+        match key {
+            PixelKey::Script => self.sort_script.key(pixel).unwrap_or(0.0),
+            key => key.key_with_hue_origin(pixel, self.hue_origin),
+        }
+    }
+
+    /// Runs the scripts in use over every colour in `pixels` ahead of sorting, see
+    /// [`PixelScript::prepare`].
+    pub fn prepare(&mut self, pixels: &[Pixel]) {
+        if self.threshold_method == PixelKey::Script {
+            self.threshold_script.prepare(pixels);
+        }
+        if self.sort_keys().iter().any(|key| key.key == PixelKey::Script) {
+            self.sort_script.prepare(pixels);
+        }
+    }
+
+    /// The first problem with a script in use, see [`PixelScript::check`].
+    pub fn script_error(&self) -> Option<String> {
+        let threshold_error = (self.threshold_method == PixelKey::Script)
+            .then(|| self.threshold_script.check(true))
+            .flatten();
+        let sort_error = self
+            .sort_keys()
+            .iter()
+            .any(|key| key.key == PixelKey::Script)
+            .then(|| self.sort_script.check(false))
+            .flatten();
+        threshold_error.or(sort_error)
+    }
+
+    /// `sorting_method` followed by every tie-breaker.
+    pub fn sort_keys(&self) -> Vec<SortKey> {
+        let primary = SortKey {
+            key: self.sorting_method,
+            descending: self.descending,
+        };
+        std::iter::once(primary).chain(self.tie_breakers.iter().copied()).collect()
+    }
+
+    /// Sorts a span by every sort key in turn.
+    ///
+    /// The keys of every pixel are worked out once up front rather than on every comparison.
+    fn sort_span(&self, span: &mut [Pixel]) {
+        let keys = self.sort_keys();
+        let values = span
+            .iter()
+            .flat_map(|pixel| {
+                keys.iter().map(|key| self.key(key.key, pixel))
+            })
+            .collect::<Vec<_>>();
+        let values_of = |i: usize| &values[i * keys.len()..(i + 1) * keys.len()];
+
+        let compare = |&a: &usize, &b: &usize| {
+            keys.iter()
+                .zip(values_of(a).iter().zip(values_of(b)))
+                .map(|(key, (a, b))| {
+                    let order = a.total_cmp(b);
+                    if key.descending { order.reverse() } else { order }
+                })
+                .find(|order| order.is_ne())
+                .unwrap_or(Ordering::Equal)
+        };
+
+        let mut order = (0..span.len()).collect::<Vec<_>>();
+        if self.stable {
+            order.par_sort_by(compare);
+        } else {
+            order.par_sort_unstable_by(compare);
+        }
+
+        let sorted = order.iter().map(|&i| span[i]).collect::<Vec<_>>();
+        span.copy_from_slice(&sorted);
+    }
+
+    pub fn in_threshold(&self, pixel: &Pixel) -> bool {
+        if self.threshold_method == PixelKey::Script {
+            return self.threshold_script.predicate(pixel).unwrap_or(false);
+        }
+
+        let key = self.threshold_key(pixel);
+        self.threshold.contains(&key)
+    }
+}
+
+impl SortMethod<Pixel, ()> for SpanSortMethod {
+    fn sort(&self, pixels: &mut [Pixel]) {
         let spans = pixels.par_split_mut(|v| {
-            let is_in_threshold = self.config.threshold.contains(&(self.config.threshold_method)(v));
-            return if self.config.invert_threshold {
+            let is_in_threshold = self.config.in_threshold(v);
+            if self.config.invert_threshold {
                 is_in_threshold
             } else {
                 !is_in_threshold
-            };
+            }
         });
 
-        spans.for_each(|span| {
-            span.par_sort_unstable_by(|a, b| (self.config.sorting_method)(a).cmp(&(self.config.sorting_method)(b)))
-        });
+        spans.for_each(|span| self.config.sort_span(span));
+    }
+
+    fn prepare(&mut self, pixels: &[Pixel]) {
+        self.config.prepare(pixels);
     }
 
+    #[cfg(feature = "gui")]
     fn ui(&mut self, ui: &mut Ui) {
-        let min = Slider::new(&mut self.config.threshold.start, 0..=255)
+        let (mut start, mut end) = self.config.threshold.clone().into_inner();
+        let min = Slider::new(&mut start, 0.0..=1.0)
             .text("Lower bound of threshold")
-            .drag_value_speed(0.1);
+            .drag_value_speed(0.001);
         ui.add(min);
-        let max = Slider::new(&mut self.config.threshold.end, 0..=255)
+        let max = Slider::new(&mut end, 0.0..=1.0)
             .text("Upper bound of threshold")
-            .drag_value_speed(0.1);
+            .drag_value_speed(0.001);
         ui.add(max);
+        self.config.threshold = start..=end;
 
-
-        let threshold_method = ComboBox::new(format!("threshold-{:?}", self.config.id), "Determine threshold value via")
-            .selected_text(self.config.threshold_method_name.clone())
+        ComboBox::new(format!("threshold-{:?}", self.config.id), "Determine threshold value via")
+            .selected_text(self.config.threshold_method.to_string())
             .show_ui(ui, |ui| {
-                {
-                    let name = "Average";
-                    if ui.selectable_value(&mut self.config.threshold_method, average, name).clicked() {
-                        self.config.threshold_method_name = name.to_string();
-                    }
-
-                    let name = "Luminosity";
-                    if ui.selectable_value(&mut self.config.threshold_method, luminosity, name).clicked() {
-                        self.config.threshold_method_name = name.to_string();
-                    }
-
-                    let name = "Red";
-                    if ui.selectable_value(&mut self.config.threshold_method, red, name).clicked() {
-                        self.config.threshold_method_name = name.to_string();
-                    }
-
-                    let name = "Green";
-                    if ui.selectable_value(&mut self.config.threshold_method, green, name).clicked() {
-                        self.config.threshold_method_name = name.to_string();
-                    }
-
-                    let name = "Blue";
-                    if ui.selectable_value(&mut self.config.threshold_method, blue, name).clicked() {
-                        self.config.threshold_method_name = name.to_string();
-                    }
+                for key in PixelKey::ALL {
+                    ui.selectable_value(&mut self.config.threshold_method, key, key.to_string());
                 }
             });
 
-        let sorting_method = ComboBox::new(format!("sort-{:?}", self.config.id), "Sort by")
-            .selected_text(self.config.sorting_method_name.clone())
+        ComboBox::new(format!("sort-{:?}", self.config.id), "Sort by")
+            .selected_text(self.config.sorting_method.to_string())
             .show_ui(ui, |ui| {
-                {
-                    let name = "Average";
-                    if ui.selectable_value(&mut self.config.sorting_method, average, name).clicked() {
-                        self.config.sorting_method_name = name.to_string();
-                    }
-
-                    let name = "Luminosity";
-                    if ui.selectable_value(&mut self.config.sorting_method, luminosity, name).clicked() {
-                        self.config.sorting_method_name = name.to_string();
-                    }
-
-                    let name = "Red";
-                    if ui.selectable_value(&mut self.config.sorting_method, red, name).clicked() {
-                        self.config.sorting_method_name = name.to_string();
-                    }
-
-                    let name = "Green";
-                    if ui.selectable_value(&mut self.config.sorting_method, green, name).clicked() {
-                        self.config.sorting_method_name = name.to_string();
-                    }
-
-                    let name = "Blue";
-                    if ui.selectable_value(&mut self.config.sorting_method, blue, name).clicked() {
-                        self.config.sorting_method_name = name.to_string();
-                    }
+                for key in PixelKey::ALL {
+                    ui.selectable_value(&mut self.config.sorting_method, key, key.to_string());
                 }
             });
 
+        ui.checkbox(&mut self.config.descending, "Descending?");
+
+        let mut to_remove = None;
+        for (i, tie_breaker) in self.config.tie_breakers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ComboBox::new(format!("tie-breaker-{}-{:?}", i, self.config.id), "Then by")
+                    .selected_text(tie_breaker.key.to_string())
+                    .show_ui(ui, |ui| {
+                        for key in PixelKey::ALL {
+                            ui.selectable_value(&mut tie_breaker.key, key, key.to_string());
+                        }
+                    });
+                ui.checkbox(&mut tie_breaker.descending, "Descending?");
+                if ui.small_button("Remove").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = to_remove {
+            self.config.tie_breakers.remove(i);
+        }
+        if ui.button("Add tie-breaker").clicked() {
+            self.config.tie_breakers.push(SortKey::default());
+        }
+        ui.checkbox(&mut self.config.stable, "Keep equal pixels in order?");
+
+        let mut keys = vec![self.config.threshold_method];
+        keys.extend(self.config.sort_keys().iter().map(|key| key.key));
+        if keys.contains(&PixelKey::Hue) || keys.contains(&PixelKey::OklchHue) {
+            let hue_origin = Slider::new(&mut self.config.hue_origin, 0.0..=360.0)
+                .text("Hue origin")
+                .drag_value_speed(0.1);
+            ui.add(hue_origin);
+        }
+
+        if self.config.threshold_method == PixelKey::Script {
+            ui.label("Threshold script");
+            script_editor(ui, &mut self.config.threshold_script, true);
+        }
+        if keys[1..].contains(&PixelKey::Script) {
+            ui.label("Sort script");
+            script_editor(ui, &mut self.config.sort_script, false);
+        }
+
         ui.checkbox(&mut self.config.invert_threshold, "Invert threshold range?");
     }
 }
 
+/// A code editor for `script` that recompiles it whenever it's changed and shows what's wrong
+/// with it underneath.
+#[cfg(feature = "gui")]
+fn script_editor(ui: &mut Ui, script: &mut PixelScript, predicate: bool) {
+    let mut source = script.source().to_string();
+    let editor = TextEdit::multiline(&mut source)
+        .code_editor()
+        .desired_rows(2)
+        .desired_width(f32::INFINITY);
+    if ui.add(editor).changed() {
+        *script = PixelScript::new(source);
+    }
+
+    if let Some(error) = script.check(predicate) {
+        ui.colored_label(Color32::LIGHT_RED, error);
+    }
+}
+
 impl Animateable for SpanSortMethod {
     fn lerp(&mut self, target: &Self, weight: f32) {
         let config = &mut self.config;
         let target_config = &target.config;
 
-        let new_threshold = {
-            let threshold = &config.threshold;
-            let target_threshold = &target_config.threshold;
-            let start_dif = target_threshold.start as f32 - threshold.start as f32;
-            let end_dif = target_threshold.end as f32 - threshold.end as f32;
-            let new_start = (if start_dif.is_sign_positive() {
-                (threshold.start as f32 + start_dif * weight).ceil()
-            } else {
-                (threshold.start as f32 + start_dif * weight).floor()
-            }) as u8;
-            let new_end = (if end_dif.is_sign_positive() {
-                (threshold.end as f32 + end_dif * weight).ceil()
-            } else {
-                (threshold.end as f32 + end_dif * weight).floor()
-            }) as u8;
-
-            new_start..new_end
-        };
-
-        config.threshold = new_threshold;
+        let (start, end) = config.threshold.clone().into_inner();
+        let (target_start, target_end) = target_config.threshold.clone().into_inner();
+        config.threshold =
+            start + (target_start - start) * weight..=end + (target_end - end) * weight;
+        config.hue_origin += (target_config.hue_origin - config.hue_origin) * weight;
     }
 }
-
-fn average(pixel: &Color32) -> u8 {
-    let [r, g, b, _] = pixel.to_array();
-
-    let average = r / 3 + g / 3 + b / 3;
-
-    return average;
-}
-
-fn luminosity(pixel: &Color32) -> u8 {
-    let [r, g, b, _] = pixel.to_array();
-
-    let luminosity = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32);
-
-    return luminosity.floor() as u8;
-}
-
-
-fn red(pixel: &Color32) -> u8 {
-    return pixel.r();
-}
-
-fn green(pixel: &Color32) -> u8 {
-    return pixel.g();
-}
-
-fn blue(pixel: &Color32) -> u8 {
-    return pixel.b();
-}
-
@@ -1,68 +1,55 @@
 use crate::sorter::animation::Animateable;
-use crate::sorter::sorters::Sorter;
-use crate::sorter::SortMethod;
-use egui::{Color32, ColorImage, Slider, Ui};
+use crate::sorter::sorters::{pixels_mut, sort_lines, ColumnSorter, ScanlineSorter, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Slider, Ui};
+use image::RgbaImage;
 use rayon::prelude::*;
-#[derive(Clone)]
+use serde::{Deserialize, Serialize};
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct AngledSorter {
     pub angle: f32,
+    /// Blend sorted pixels with their neighbour across the line to hide the staircase.
+    #[serde(default)]
+    pub smooth_edges: bool,
 }
 
-//This code is absolutely horrible and causes undefined behavior
-//but it makes things go fast and it doesn't crash immediately so until I
-//decide to make a fix it's staying like this
-impl Sorter<Color32, &mut ColorImage, (), ()> for AngledSorter {
-    fn sort_image(&self, image: &mut ColorImage, sorter: impl SortMethod<Color32, ()>) -> () {
-        let pixels: &mut Vec<Color32> = image.pixels.as_mut();
-        let pixels_pointer = unsafe {
-            let temp = std::ptr::read(pixels);
-            let ptr = temp.into_raw_parts().0;
-            ptr as usize
-        };
-        let [w, h] = image.size;
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for AngledSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+
+        let (angle_sin, angle_cos) = self.angle.to_radians().sin_cos();
 
-        if self.angle % 90.0 == 0.0 && self.angle != 0.0 {
-            return;
+        //Straight lines are handed to the row and column sorters, which don't need the tangent.
+        if angle_cos.abs() < f32::EPSILON {
+            let reverse = angle_sin < 0.0;
+            return ColumnSorter { reverse, ..Default::default() }.sort_image(image, sorter);
+        }
+        if angle_sin.abs() < f32::EPSILON {
+            let reverse = angle_cos < 0.0;
+            return ScanlineSorter { reverse, ..Default::default() }.sort_image(image, sorter);
         }
 
-        let angle_tan = self.angle.to_radians().tan();
+        let walk = LineWalk::new(angle_sin, angle_cos, w, h);
+        let lines = walk.lines();
 
-        let extra_height = (angle_tan * w as f32).floor() as i64;
-        let range = if extra_height > 0 {
-            -extra_height..h as i64
+        let pixels = pixels_mut(image);
+        if self.smooth_edges {
+            let original = pixels.to_vec();
+            sort_lines(pixels, &lines, &sorter);
+            walk.smooth_edges(pixels, &original);
         } else {
-            0..(h as i64 - extra_height)
-        };
-
-        range.par_bridge().for_each(|row| {
-            let idxes = (0..w)
-                .map(|xv| (xv, (xv as f32 * angle_tan + row as f32) as usize))
-                .filter(|(_, y)| *y > 0 && *y < h)
-                .collect::<Vec<_>>();
-
-            let mut angled_pixels = idxes
-                .iter()
-                .map(|(x, y)| unsafe { *pixels.get_unchecked(y * w + x) })
-                .collect::<Vec<_>>();
-            sorter.sort(&mut angled_pixels[..]);
-
-            for (i, (x, y)) in idxes.iter().enumerate() {
-                unsafe {
-                    std::ptr::write(
-                        (pixels_pointer + (y * w + x) * std::mem::size_of::<Color32>())
-                            as *mut Color32,
-                        *angled_pixels.get_unchecked(i),
-                    );
-                }
-            }
-        });
+            sort_lines(pixels, &lines, &sorter);
+        }
     }
 
+    #[cfg(feature = "gui")]
     fn ui(&mut self, ui: &mut Ui) {
         let angle = Slider::new(&mut self.angle, 0.0..=360.0)
             .text("Angle to sort at")
             .drag_value_speed(0.1);
         ui.add(angle);
+        ui.checkbox(&mut self.smooth_edges, "Smooth line edges?");
     }
 }
 
@@ -71,3 +58,187 @@ impl Animateable for AngledSorter {
         self.angle += ( target.angle - self.angle) * weight;
     }
 }
+
+/// Parallel lines across the image, walked one pixel per step along the major axis.
+///
+/// Shallow lines step in x and steep lines step in y. Along the major axis every line is
+/// offset by a whole pixel from its neighbour, so every pixel lands in exactly one line.
+struct LineWalk {
+    /// Offset along the minor axis at each position on the major axis.
+    offsets: Vec<i64>,
+    /// Fractional part of each offset, used when smoothing edges.
+    fractions: Vec<f32>,
+    steep: bool,
+    reverse: bool,
+    w: usize,
+    h: usize,
+}
+
+impl LineWalk {
+    fn new(angle_sin: f32, angle_cos: f32, w: usize, h: usize) -> Self {
+        let steep = angle_sin.abs() > angle_cos.abs();
+        let (slope, major_len, reverse) = if steep {
+            (angle_cos as f64 / angle_sin as f64, h, angle_sin < 0.0)
+        } else {
+            (angle_sin as f64 / angle_cos as f64, w, angle_cos < 0.0)
+        };
+
+        let exact = (0..major_len).map(|i| i as f64 * slope);
+        let offsets = exact.clone().map(|offset| offset.floor() as i64).collect();
+        let fractions = exact.map(|offset| (offset - offset.floor()) as f32).collect();
+
+        LineWalk {
+            offsets,
+            fractions,
+            steep,
+            reverse,
+            w,
+            h,
+        }
+    }
+
+    fn index(&self, major: usize, minor: usize) -> usize {
+        if self.steep {
+            major * self.w + minor
+        } else {
+            minor * self.w + major
+        }
+    }
+
+    fn lines(&self) -> Vec<Vec<usize>> {
+        let minor_len = if self.steep { self.w } else { self.h } as i64;
+        let first_offset = *self.offsets.first().unwrap_or(&0);
+        let last_offset = *self.offsets.last().unwrap_or(&0);
+
+        //The line starting at `start` covers `start + offset` on the minor axis, so these
+        //starts are exactly the ones that touch the image.
+        let starts = -first_offset.max(last_offset)..minor_len - first_offset.min(last_offset);
+
+        starts
+            .into_par_iter()
+            .map(|start| {
+                let mut line = self
+                    .offsets
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(major, offset)| {
+                        let minor = start + offset;
+                        (0..minor_len)
+                            .contains(&minor)
+                            .then(|| self.index(major, minor as usize))
+                    })
+                    .collect::<Vec<_>>();
+                if self.reverse {
+                    line.reverse();
+                }
+                line
+            })
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    /// Shifts every sorted pixel towards where its line actually passes through, by blending it
+    /// with the pixel one step back on the minor axis. Pixels the sort didn't touch are left alone.
+    fn smooth_edges(&self, pixels: &mut [Pixel], original: &[Pixel]) {
+        let sorted = pixels.to_vec();
+
+        pixels.par_iter_mut().enumerate().for_each(|(i, pixel)| {
+            let (x, y) = (i % self.w, i / self.w);
+            let (major, minor) = if self.steep { (y, x) } else { (x, y) };
+            if minor == 0 {
+                return;
+            }
+
+            let neighbour = self.index(major, minor - 1);
+            if sorted[i] == original[i] && sorted[neighbour] == original[neighbour] {
+                return;
+            }
+
+            let weight = self.fractions[major];
+            *pixel = blend(&sorted[i], &sorted[neighbour], weight);
+        });
+    }
+}
+
+fn blend(a: &Pixel, b: &Pixel, weight: f32) -> Pixel {
+    std::array::from_fn(|channel| {
+        (a[channel] as f32 * (1.0 - weight) + b[channel] as f32 * weight).round() as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const ANGLES: [f32; 14] = [
+        0.0, 1.0, 30.0, 45.0, 60.0, 89.9, 90.0, 135.0, 180.0, 225.0, 270.0, 300.0, 359.0, -45.0,
+    ];
+
+    fn walk(angle: f32, w: usize, h: usize) -> LineWalk {
+        let (angle_sin, angle_cos) = angle.to_radians().sin_cos();
+        LineWalk::new(angle_sin, angle_cos, w, h)
+    }
+
+    /// Leaves every line exactly as it is.
+    #[derive(Clone)]
+    struct KeepOrder;
+
+    impl SortMethod<Pixel, ()> for KeepOrder {
+        fn sort(&self, _pixels: &mut [Pixel]) {}
+
+        #[cfg(feature = "gui")]
+        fn ui(&mut self, _ui: &mut Ui) {}
+    }
+
+    #[test]
+    fn lines_cover_every_pixel_once() {
+        let (w, h) = (37, 23);
+        for angle in ANGLES {
+            let mut visits = vec![0; w * h];
+            for i in walk(angle, w, h).lines().into_iter().flatten() {
+                visits[i] += 1;
+            }
+            assert!(visits.iter().all(|&count| count == 1), "{angle} degrees");
+        }
+    }
+
+    #[test]
+    fn smooth_edges_leaves_untouched_pixels_alone() {
+        let (w, h) = (37, 23);
+        let original = RgbaImage::from_fn(w as u32, h as u32, |x, y| {
+            Rgba([(x * 7) as u8, (y * 11) as u8, (x * y) as u8, 255])
+        });
+
+        for angle in ANGLES {
+            let mut image = original.clone();
+            AngledSorter { angle, smooth_edges: true }.sort_image(&mut image, KeepOrder);
+            assert!(image == original, "{angle} degrees");
+
+            //Reverse a single line, only it and the pixels next to it may change.
+            let walk = walk(angle, w, h);
+            let line = walk.lines().into_iter().max_by_key(|line| line.len()).unwrap();
+            let mut pixels = original.pixels().map(|pixel| pixel.0).collect::<Vec<_>>();
+            let reversed = line.iter().rev().map(|&i| pixels[i]).collect::<Vec<_>>();
+            for (&i, pixel) in line.iter().zip(reversed) {
+                pixels[i] = pixel;
+            }
+            let sorted = pixels.clone();
+            let unsorted = original.pixels().map(|pixel| pixel.0).collect::<Vec<_>>();
+            walk.smooth_edges(&mut pixels, &unsorted);
+
+            for (i, pixel) in pixels.iter().enumerate() {
+                let (x, y) = (i % w, i / w);
+                let (major, minor) = if walk.steep { (y, x) } else { (x, y) };
+                let touched = sorted[i] != unsorted[i]
+                    || (minor > 0 && {
+                        let neighbour = walk.index(major, minor - 1);
+                        sorted[neighbour] != unsorted[neighbour]
+                    });
+                if !touched {
+                    assert_eq!(*pixel, unsorted[i], "{angle} degrees, pixel {i}");
+                }
+            }
+        }
+    }
+}
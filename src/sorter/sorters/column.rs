@@ -0,0 +1,46 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+use image::RgbaImage;
+
+use crate::sorter::sorters::{pixels_mut, sort_lines, ScanPattern, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ColumnSorter {
+    /// Sort columns bottom to top instead of top to bottom.
+    pub reverse: bool,
+    #[serde(default)]
+    pub pattern: ScanPattern,
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for ColumnSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+
+        let lines = (0..w)
+            .map(|x| {
+                let column = (0..h).map(|y| y * w + x);
+                if self.pattern.flips(x, self.reverse) {
+                    column.rev().collect()
+                } else {
+                    column.collect()
+                }
+            })
+            .collect::<Vec<Vec<_>>>();
+
+        let lines = if self.pattern == ScanPattern::Snake {
+            vec![lines.concat()]
+        } else {
+            lines
+        };
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.reverse, "Bottom to top?");
+        self.pattern.ui(ui);
+    }
+}
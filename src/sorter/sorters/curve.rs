@@ -0,0 +1,143 @@
+#[cfg(feature = "gui")]
+use egui::{ComboBox, Slider, Ui};
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sorter::sorters::{pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+
+/// Sorts along a space-filling curve, either one curve over the whole image or one per tile.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CurveSorter {
+    pub curve: CurveKind,
+    /// Cover the image with square tiles that each get their own curve.
+    pub tiled: bool,
+    /// Side length of a tile in pixels, always a power of two.
+    pub tile_size: u32,
+
+    #[cfg(feature = "gui")]
+    #[serde(skip, default = "rand::random")]
+    id: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CurveKind {
+    #[default]
+    Hilbert,
+    /// Morton order, which recursively visits quadrants in a Z shape.
+    ZOrder,
+}
+
+impl Default for CurveSorter {
+    fn default() -> Self {
+        CurveSorter {
+            curve: CurveKind::Hilbert,
+            tiled: true,
+            tile_size: 64,
+            #[cfg(feature = "gui")]
+            id: rand::random(),
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for CurveSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+
+        let side = if self.tiled {
+            self.tile_size.max(1).next_power_of_two() as usize
+        } else {
+            w.max(h).next_power_of_two()
+        };
+
+        let tiles = (0..h.div_ceil(side))
+            .flat_map(|ty| (0..w.div_ceil(side)).map(move |tx| (tx * side, ty * side)))
+            .collect::<Vec<_>>();
+
+        let lines = tiles
+            .into_par_iter()
+            .map(|(left, top)| {
+                (0..side * side)
+                    .filter_map(|d| {
+                        let (x, y) = self.curve.point(d, side);
+                        let (x, y) = (left + x, top + y);
+                        (x < w && y < h).then_some(y * w + x)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ComboBox::new(format!("curve-{:?}", self.id), "Curve")
+            .selected_text(self.curve.name())
+            .show_ui(ui, |ui| {
+                for curve in [CurveKind::Hilbert, CurveKind::ZOrder] {
+                    ui.selectable_value(&mut self.curve, curve, curve.name());
+                }
+            });
+        ui.checkbox(&mut self.tiled, "Split into tiles?");
+        if self.tiled {
+            let mut power = self.tile_size.max(1).next_power_of_two().trailing_zeros();
+            ui.add(
+                Slider::new(&mut power, 1..=11)
+                    .text("Tile size")
+                    .custom_formatter(|power, _| format!("{}px", 1u32 << power as u32)),
+            );
+            self.tile_size = 1 << power;
+        }
+    }
+}
+
+impl CurveKind {
+    #[cfg(feature = "gui")]
+    fn name(&self) -> &'static str {
+        match self {
+            CurveKind::Hilbert => "Hilbert",
+            CurveKind::ZOrder => "Z-order",
+        }
+    }
+
+    /// Position of the `d`th point along the curve filling a `side` by `side` square, where
+    /// `side` is a power of two.
+    fn point(&self, d: usize, side: usize) -> (usize, usize) {
+        match self {
+            CurveKind::Hilbert => hilbert_point(d, side),
+            CurveKind::ZOrder => (compact_bits(d), compact_bits(d >> 1)),
+        }
+    }
+}
+
+fn hilbert_point(d: usize, side: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+
+        //Rotate the quadrant so the sub-curves join up end to end.
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+/// Gathers every even bit of `n` into the low bits of the result.
+fn compact_bits(n: usize) -> usize {
+    (0..usize::BITS / 2).fold(0, |out, bit| out | ((n >> (2 * bit)) & 1) << bit)
+}
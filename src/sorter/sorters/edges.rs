@@ -0,0 +1,181 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::streamlines::trace_streamlines;
+use crate::sorter::sorters::{pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, PixelKey, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{ComboBox, Slider, Ui};
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Sorts along lines that follow the edges in the image itself.
+///
+/// The local edge direction comes from the structure tensor of the image's Sobel gradient,
+/// blurred so that lines bend smoothly around contours instead of jittering on noise.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EdgeSorter {
+    pub direction: EdgeDirection,
+    /// Radius of the blur applied to the structure tensor, larger values follow broader shapes.
+    pub smoothing: u32,
+    /// Extra rotation applied on top of the edge direction, in degrees.
+    pub rotation: f32,
+    /// Distance travelled per step while tracing a line, in pixels.
+    pub step_length: f32,
+    /// Longest a single line is allowed to get, in pixels.
+    pub max_length: f32,
+
+    #[cfg(feature = "gui")]
+    #[serde(skip, default = "rand::random")]
+    id: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EdgeDirection {
+    /// Run along contours.
+    #[default]
+    Along,
+    /// Run straight through contours.
+    Across,
+}
+
+impl Default for EdgeSorter {
+    fn default() -> Self {
+        EdgeSorter {
+            direction: EdgeDirection::Along,
+            smoothing: 4,
+            rotation: 0.0,
+            step_length: 0.5,
+            max_length: 1000.0,
+            #[cfg(feature = "gui")]
+            id: rand::random(),
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for EdgeSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        let pixels = pixels_mut(image);
+        let brightness = pixels
+            .par_iter()
+            .map(|pixel| PixelKey::Luminosity.key(pixel))
+            .collect::<Vec<_>>();
+
+        let tensor = structure_tensor(&brightness, w, h);
+        let radius = self.smoothing as usize;
+        let [xx, xy, yy] = tensor.map(|component| box_blur(&component, w, h, radius));
+
+        let offset = match self.direction {
+            EdgeDirection::Along => FRAC_PI_2,
+            EdgeDirection::Across => 0.0,
+        } + self.rotation.to_radians();
+
+        //The dominant gradient orientation is half the angle of the tensor's (xx - yy, 2xy).
+        let angles = (0..w * h)
+            .into_par_iter()
+            .map(|i| 0.5 * (2.0 * xy[i]).atan2(xx[i] - yy[i]) + offset)
+            .collect::<Vec<_>>();
+
+        let lines = trace_streamlines(w, h, &angles, self.step_length, self.max_length);
+        sort_lines(pixels, &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ComboBox::new(format!("edge-direction-{:?}", self.id), "Line direction")
+            .selected_text(match self.direction {
+                EdgeDirection::Along => "Along edges",
+                EdgeDirection::Across => "Across edges",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.direction, EdgeDirection::Along, "Along edges");
+                ui.selectable_value(&mut self.direction, EdgeDirection::Across, "Across edges");
+            });
+        ui.add(Slider::new(&mut self.smoothing, 0..=32).text("Edge smoothing"));
+        ui.add(
+            Slider::new(&mut self.rotation, -90.0..=90.0)
+                .text("Extra rotation")
+                .drag_value_speed(0.1),
+        );
+        ui.add(Slider::new(&mut self.step_length, 0.1..=5.0).text("Step length"));
+        ui.add(
+            Slider::new(&mut self.max_length, 10.0..=10000.0)
+                .logarithmic(true)
+                .text("Maximum line length"),
+        );
+    }
+}
+
+impl Animateable for EdgeSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        self.rotation += (target.rotation - self.rotation) * weight;
+        self.max_length += (target.max_length - self.max_length) * weight;
+    }
+}
+
+/// The per-pixel products of the Sobel gradient: `[gx * gx, gx * gy, gy * gy]`.
+fn structure_tensor(brightness: &[f32], w: usize, h: usize) -> [Vec<f32>; 3] {
+    let at = |x: usize, y: usize, dx: isize, dy: isize| {
+        let x = (x as isize + dx).clamp(0, w as isize - 1) as usize;
+        let y = (y as isize + dy).clamp(0, h as isize - 1) as usize;
+        brightness[y * w + x]
+    };
+
+    let products = (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            let gx = at(x, y, 1, -1) + 2.0 * at(x, y, 1, 0) + at(x, y, 1, 1)
+                - at(x, y, -1, -1)
+                - 2.0 * at(x, y, -1, 0)
+                - at(x, y, -1, 1);
+            let gy = at(x, y, -1, 1) + 2.0 * at(x, y, 0, 1) + at(x, y, 1, 1)
+                - at(x, y, -1, -1)
+                - 2.0 * at(x, y, 0, -1)
+                - at(x, y, 1, -1);
+            [gx * gx, gx * gy, gy * gy]
+        })
+        .collect::<Vec<_>>();
+
+    std::array::from_fn(|component| products.iter().map(|p| p[component]).collect())
+}
+
+/// Separable box blur with clamped edges.
+fn box_blur(values: &[f32], w: usize, h: usize, radius: usize) -> Vec<f32> {
+    if radius == 0 {
+        return values.to_vec();
+    }
+
+    let rows = values
+        .par_chunks(w)
+        .flat_map_iter(|row| blur_line(|x| row[x], w, radius))
+        .collect::<Vec<_>>();
+
+    let columns = (0..w)
+        .into_par_iter()
+        .map(|x| blur_line(|y| rows[y * w + x], h, radius))
+        .collect::<Vec<_>>();
+
+    (0..w * h).map(|i| columns[i % w][i / w]).collect()
+}
+
+/// Running-sum average over `2 * radius + 1` values, repeating the end values past the edges.
+fn blur_line(get: impl Fn(usize) -> f32, len: usize, radius: usize) -> Vec<f32> {
+    let clamped = |i: isize| get(i.clamp(0, len as isize - 1) as usize);
+    let radius = radius as isize;
+
+    let mut sum = (-radius..=radius).map(clamped).sum::<f32>();
+    (0..len as isize)
+        .map(|i| {
+            let average = sum / (2 * radius + 1) as f32;
+            sum += clamped(i + radius + 1) - clamped(i - radius);
+            average
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+use std::f32::consts::TAU;
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::noise::Perlin;
+use crate::sorter::sorters::streamlines::trace_streamlines;
+use crate::sorter::sorters::{pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{DragValue, Slider, Ui};
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Sorts along streamlines of a noise flow field.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FlowFieldSorter {
+    /// How zoomed in the noise is, smaller values give wider, smoother curves.
+    pub noise_scale: f32,
+    pub octaves: u32,
+    pub seed: u64,
+    /// Distance travelled per step while tracing a line, in pixels.
+    pub step_length: f32,
+    /// Longest a single line is allowed to get, in pixels.
+    pub max_length: f32,
+    /// Position in the noise, moving it makes the field drift.
+    pub offset: [f32; 2],
+}
+
+impl Default for FlowFieldSorter {
+    fn default() -> Self {
+        FlowFieldSorter {
+            noise_scale: 0.003,
+            octaves: 3,
+            seed: 0,
+            step_length: 0.5,
+            max_length: 1000.0,
+            offset: [0.0, 0.0],
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for FlowFieldSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let noise = Perlin::new(self.seed);
+
+        let angles = (0..w * h)
+            .into_par_iter()
+            .map(|i| {
+                let x = (i % w) as f32 * self.noise_scale + self.offset[0];
+                let y = (i / w) as f32 * self.noise_scale + self.offset[1];
+                noise.fractal(x, y, self.octaves) * TAU
+            })
+            .collect::<Vec<_>>();
+
+        let lines = trace_streamlines(w, h, &angles, self.step_length, self.max_length);
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            Slider::new(&mut self.noise_scale, 0.0001..=0.1)
+                .logarithmic(true)
+                .text("Noise scale"),
+        );
+        ui.add(Slider::new(&mut self.octaves, 1..=8).text("Octaves"));
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut self.seed));
+            ui.label("Seed");
+        });
+        ui.add(Slider::new(&mut self.step_length, 0.1..=5.0).text("Step length"));
+        ui.add(
+            Slider::new(&mut self.max_length, 10.0..=10000.0)
+                .logarithmic(true)
+                .text("Maximum line length"),
+        );
+        ui.add(Slider::new(&mut self.offset[0], -10.0..=10.0).text("Noise offset X"));
+        ui.add(Slider::new(&mut self.offset[1], -10.0..=10.0).text("Noise offset Y"));
+    }
+}
+
+impl Animateable for FlowFieldSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        for (offset, target_offset) in self.offset.iter_mut().zip(target.offset) {
+            *offset += (target_offset - *offset) * weight;
+        }
+        self.noise_scale += (target.noise_scale - self.noise_scale) * weight;
+    }
+}
@@ -1,11 +1,42 @@
 mod angled;
+mod column;
+mod curve;
+mod edges;
+mod flow;
+mod noise;
+mod path;
+mod radial;
+mod regions;
+mod rings;
 mod scanline;
+mod spiral;
+mod streamlines;
+mod temporal;
+mod tiles;
+mod voronoi;
+mod wave;
 
-use crate::sorter::{Animateable, SortMethod};
+use crate::sorter::{Animateable, Pixel, SortMethod};
 pub use angled::*;
-use eframe::epaint::{Color32, ColorImage};
+pub use column::*;
+#[cfg(feature = "gui")]
 use egui::Ui;
+use image::RgbaImage;
+use rayon::prelude::*;
+pub use curve::*;
+pub use edges::*;
+pub use flow::*;
+pub use path::*;
+pub use radial::*;
+pub use regions::*;
+pub use rings::*;
 pub use scanline::*;
+pub use spiral::*;
+pub use temporal::*;
+pub use tiles::*;
+pub use voronoi::*;
+pub use wave::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 
 //T represents our pixels, A represents the image.
@@ -19,31 +50,139 @@ pub trait Sorter<PixelType, ImageType, ReturnType, SortReturnType> {
         sorter: impl SortMethod<PixelType, SortReturnType>,
     ) -> ReturnType;
 
+    #[cfg(feature = "gui")]
     fn ui(&mut self, ui: &mut Ui);
 }
 
-#[derive(Clone)]
+/// Views the raw bytes of an image as pixels.
+pub(crate) fn pixels_mut(image: &mut RgbaImage) -> &mut [Pixel] {
+    bytemuck::cast_slice_mut(image)
+}
+
+/// Views the raw bytes of an image as pixels, without being able to change them.
+pub(crate) fn pixels(image: &RgbaImage) -> &[Pixel] {
+    bytemuck::cast_slice(image)
+}
+
+/// Gathers each line of pixel indices, sorts the lines in parallel and scatters them back.
+///
+/// Lines are expected to be disjoint, a pixel that shows up in two lines ends up with
+/// whichever line was written last.
+pub(crate) fn sort_lines(
+    pixels: &mut [Pixel],
+    lines: &[Vec<usize>],
+    sorter: &impl SortMethod<Pixel, ()>,
+) {
+    let sorted_lines = lines
+        .par_iter()
+        .map(|line| {
+            let mut line_pixels = line.iter().map(|&i| pixels[i]).collect::<Vec<_>>();
+            sorter.sort(&mut line_pixels);
+            line_pixels
+        })
+        .collect::<Vec<_>>();
+
+    for (line, line_pixels) in lines.iter().zip(sorted_lines) {
+        for (&i, pixel) in line.iter().zip(line_pixels) {
+            pixels[i] = pixel;
+        }
+    }
+}
+
+/// Builds lines by tagging every pixel with the line it belongs to and its position along it.
+///
+/// Each pixel is only ever given one tag, so the lines are always disjoint. Pixels tagged
+/// with `None` are left out of every line.
+pub(crate) fn lines_from_keys<K: Ord + Send>(
+    w: usize,
+    h: usize,
+    key: impl Fn(usize, usize) -> Option<(K, f32)> + Sync,
+) -> Vec<Vec<usize>> {
+    let mut keyed = (0..w * h)
+        .into_par_iter()
+        .filter_map(|i| key(i % w, i / w).map(|(line, position)| (line, position, i)))
+        .collect::<Vec<_>>();
+    keyed.par_sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    keyed
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|line| line.iter().map(|&(_, _, i)| i).collect())
+        .collect()
+}
+
+/// Turns `(x, y)` into `(along, across)` lines running at an angle, given as its sine and cosine.
+pub(crate) fn rotate(x: f32, y: f32, sin: f32, cos: f32) -> (f32, f32) {
+    (x * cos + y * sin, y * cos - x * sin)
+}
+
+/// Key for [`lines_from_keys`] that puts `(x, y)` on one of the one pixel wide straight lines at
+/// an angle, with its position along that line.
+pub(crate) fn rotated_band(x: f32, y: f32, sin: f32, cos: f32) -> (i64, f32) {
+    let (along, across) = rotate(x, y, sin, cos);
+    (across.floor() as i64, along)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AvailableLineAlgos {
     Scanline(ScanlineSorter),
+    Column(ColumnSorter),
     Angled(AngledSorter),
+    Radial(RadialSorter),
+    Rings(RingSorter),
+    Spiral(SpiralSorter),
+    Wave(WaveSorter),
+    FlowField(FlowFieldSorter),
+    Edges(EdgeSorter),
+    Curve(CurveSorter),
+    Path(PathSorter),
+    Voronoi(VoronoiSorter),
+    Tiles(TileSorter),
+    Regions(RegionSorter),
 }
 
-impl Sorter<Color32, &mut ColorImage, (), ()> for AvailableLineAlgos {
-    fn sort_image(&self, image: &mut ColorImage, sorter: impl SortMethod<Color32, ()>) -> () {
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for AvailableLineAlgos {
+    fn sort_image(&self, image: &mut RgbaImage, mut sorter: impl SortMethod<Pixel, ()>) {
+        sorter.prepare(pixels(image));
+
         match self {
             AvailableLineAlgos::Scanline(line_alg) => {
                 line_alg.sort_image(image, sorter);
             }
+            AvailableLineAlgos::Column(line_alg) => line_alg.sort_image(image, sorter),
             AvailableLineAlgos::Angled(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Radial(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Rings(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Spiral(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Wave(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::FlowField(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Edges(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Curve(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Path(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Voronoi(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Tiles(line_alg) => line_alg.sort_image(image, sorter),
+            AvailableLineAlgos::Regions(line_alg) => line_alg.sort_image(image, sorter),
         }
     }
 
+    #[cfg(feature = "gui")]
     fn ui(&mut self, ui: &mut Ui) {
         match self {
             AvailableLineAlgos::Scanline(line_alg) => {
                 line_alg.ui(ui);
             }
+            AvailableLineAlgos::Column(line_alg) => line_alg.ui(ui),
             AvailableLineAlgos::Angled(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Radial(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Rings(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Spiral(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Wave(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::FlowField(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Edges(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Curve(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Path(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Voronoi(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Tiles(line_alg) => line_alg.ui(ui),
+            AvailableLineAlgos::Regions(line_alg) => line_alg.ui(ui),
         }
     }
 }
@@ -57,6 +196,42 @@ impl Animateable for AvailableLineAlgos {
             (AvailableLineAlgos::Scanline(_), AvailableLineAlgos::Scanline(_)) => {
 
             }
+            (AvailableLineAlgos::Column(_), AvailableLineAlgos::Column(_)) => {
+
+            }
+            (AvailableLineAlgos::Radial(line_alg), AvailableLineAlgos::Radial(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Rings(line_alg), AvailableLineAlgos::Rings(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Spiral(line_alg), AvailableLineAlgos::Spiral(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Wave(line_alg), AvailableLineAlgos::Wave(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::FlowField(line_alg), AvailableLineAlgos::FlowField(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Edges(line_alg), AvailableLineAlgos::Edges(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Curve(_), AvailableLineAlgos::Curve(_)) => {
+
+            }
+            (AvailableLineAlgos::Path(line_alg), AvailableLineAlgos::Path(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Voronoi(line_alg), AvailableLineAlgos::Voronoi(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Tiles(line_alg), AvailableLineAlgos::Tiles(target)) => {
+                line_alg.lerp(target, weight)
+            },
+            (AvailableLineAlgos::Regions(line_alg), AvailableLineAlgos::Regions(target)) => {
+                line_alg.lerp(target, weight)
+            },
             _ => println!("Target and source mismatch, or lerp isn't implemented for this type!"),
         }
     }
@@ -64,7 +239,7 @@ impl Animateable for AvailableLineAlgos {
 
 impl Default for AvailableLineAlgos {
     fn default() -> Self {
-        Self::Scanline(ScanlineSorter)
+        Self::Scanline(ScanlineSorter::default())
     }
 }
 
@@ -72,7 +247,19 @@ impl Debug for AvailableLineAlgos {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let variant_name = match self {
             AvailableLineAlgos::Scanline(_) => "ScanLine",
+            AvailableLineAlgos::Column(_) => "ColumnLine",
             AvailableLineAlgos::Angled(_) => "AngledLine",
+            AvailableLineAlgos::Radial(_) => "RadialLine",
+            AvailableLineAlgos::Rings(_) => "RingLine",
+            AvailableLineAlgos::Spiral(_) => "SpiralLine",
+            AvailableLineAlgos::Wave(_) => "WaveLine",
+            AvailableLineAlgos::FlowField(_) => "FlowFieldLine",
+            AvailableLineAlgos::Edges(_) => "EdgeLine",
+            AvailableLineAlgos::Curve(_) => "CurveLine",
+            AvailableLineAlgos::Path(_) => "PathLine",
+            AvailableLineAlgos::Voronoi(_) => "VoronoiLine",
+            AvailableLineAlgos::Tiles(_) => "TileLine",
+            AvailableLineAlgos::Regions(_) => "RegionLine",
         };
         write!(f, "{}", variant_name)
     }
@@ -83,3 +270,49 @@ impl PartialEq for AvailableLineAlgos {
         std::mem::discriminant(self) == std::mem::discriminant(other)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts how often it's shown each pixel, where every pixel holds its own index, and
+    /// reverses each line so the scatter actually moves pixels around.
+    #[derive(Clone)]
+    struct CountVisits(Arc<Vec<AtomicUsize>>);
+
+    impl SortMethod<Pixel, ()> for CountVisits {
+        fn sort(&self, pixels: &mut [Pixel]) {
+            for pixel in pixels.iter() {
+                self.0[u32::from_le_bytes(*pixel) as usize].fetch_add(1, Ordering::Relaxed);
+            }
+            pixels.reverse();
+        }
+
+        #[cfg(feature = "gui")]
+        fn ui(&mut self, _ui: &mut Ui) {}
+    }
+
+    fn indexed_image(w: u32, h: u32) -> RgbaImage {
+        RgbaImage::from_fn(w, h, |x, y| Rgba((y * w + x).to_le_bytes()))
+    }
+
+    #[test]
+    fn sort_lines_reads_and_writes_every_pixel_once() {
+        let (w, h) = (7, 5);
+        let mut image = indexed_image(w, h);
+        let visits = CountVisits(Arc::new((0..w * h).map(|_| AtomicUsize::new(0)).collect()));
+
+        let pixels = pixels_mut(&mut image);
+        let lines = lines_from_keys(w as usize, h as usize, |x, y| Some((x + y, x as f32)));
+        sort_lines(pixels, &lines, &visits);
+
+        assert!(visits.0.iter().all(|count| count.load(Ordering::Relaxed) == 1));
+        let mut written = pixels.iter().map(|pixel| u32::from_le_bytes(*pixel)).collect::<Vec<_>>();
+        assert_ne!(written, (0..w * h).collect::<Vec<_>>());
+        written.sort_unstable();
+        assert_eq!(written, (0..w * h).collect::<Vec<_>>());
+    }
+}
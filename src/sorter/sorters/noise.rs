@@ -0,0 +1,79 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// Seeded 2D Perlin noise.
+pub(crate) struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub(crate) fn new(seed: u64) -> Self {
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        table.shuffle(&mut StdRng::seed_from_u64(seed));
+
+        let mut permutation = [0u8; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i % 256];
+        }
+        Perlin { permutation }
+    }
+
+    /// Noise at a point, roughly in `-1.0..=1.0`.
+    pub(crate) fn get(&self, x: f32, y: f32) -> f32 {
+        let (cell_x, cell_y) = (x.floor(), y.floor());
+        let (x, y) = (x - cell_x, y - cell_y);
+        let (cell_x, cell_y) = (cell_x as i64 & 255, cell_y as i64 & 255);
+
+        let hash = |dx: i64, dy: i64| {
+            let row = self.permutation[(cell_x + dx) as usize] as i64;
+            self.permutation[(row + cell_y + dy) as usize]
+        };
+
+        let (fade_x, fade_y) = (fade(x), fade(y));
+        let top = lerp(gradient(hash(0, 0), x, y), gradient(hash(1, 0), x - 1.0, y), fade_x);
+        let bottom = lerp(
+            gradient(hash(0, 1), x, y - 1.0),
+            gradient(hash(1, 1), x - 1.0, y - 1.0),
+            fade_x,
+        );
+        lerp(top, bottom, fade_y)
+    }
+
+    /// Several octaves of noise stacked on top of each other, each at double the frequency
+    /// and half the strength of the last.
+    pub(crate) fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut total, mut frequency, mut amplitude, mut max) = (0.0, 1.0, 1.0, 0.0);
+        for _ in 0..octaves.max(1) {
+            total += self.get(x * frequency, y * frequency) * amplitude;
+            max += amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        total / max
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => x - y,
+        2 => -x + y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
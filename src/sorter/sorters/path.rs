@@ -0,0 +1,229 @@
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Color32, Pos2, Rect, Sense, Slider, Ui};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Sorts along strokes drawn over the image.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PathSorter {
+    /// Every stroke as a list of points, with `[0, 0]` the top left and `[1, 1]` the bottom right
+    /// of the image, so strokes keep their place when the image is resized.
+    pub strokes: Vec<Vec<[f32; 2]>>,
+    /// Width of the band sorted around each stroke, in pixels. Every pixel across the band gets
+    /// its own line running alongside the stroke.
+    pub brush_width: f32,
+    /// Sort from the end of each stroke towards its start.
+    pub reverse: bool,
+}
+
+impl Default for PathSorter {
+    fn default() -> Self {
+        PathSorter {
+            strokes: vec![],
+            brush_width: 20.0,
+            reverse: false,
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for PathSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let mut claimed = vec![false; w * h];
+        let mut lines = Vec::new();
+
+        for stroke in &self.strokes {
+            let points = stroke
+                .iter()
+                .map(|[x, y]| [x * w as f32, y * h as f32])
+                .collect::<Vec<_>>();
+            if points.len() < 2 {
+                continue;
+            }
+            let normals = vertex_normals(&points);
+
+            //Work outwards from the middle of the brush, so where lines overlap the ones closest
+            //to the stroke keep their pixels.
+            let half_width = (self.brush_width / 2.0).max(0.0) as i64;
+            let offsets = (0..=half_width).flat_map(|o| if o == 0 { vec![0] } else { vec![-o, o] });
+
+            for offset in offsets {
+                let mut line = Vec::new();
+                for (position, normal) in walk(&points, &normals) {
+                    let x = position[0] + normal[0] * offset as f32;
+                    let y = position[1] + normal[1] * offset as f32;
+                    if x < 0.0 || y < 0.0 || x >= w as f32 || y >= h as f32 {
+                        continue;
+                    }
+
+                    let i = y as usize * w + x as usize;
+                    if !claimed[i] {
+                        claimed[i] = true;
+                        line.push(i);
+                    }
+                }
+
+                if self.reverse {
+                    line.reverse();
+                }
+                lines.push(line);
+            }
+        }
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.label("Drag over the image to draw a stroke.");
+        ui.add(Slider::new(&mut self.brush_width, 1.0..=500.0).text("Brush width"));
+        ui.checkbox(&mut self.reverse, "Sort from the end of each stroke?");
+        ui.horizontal(|ui| {
+            if ui.button("Undo stroke").clicked() {
+                self.strokes.pop();
+            }
+            if ui.button("Clear strokes").clicked() {
+                self.strokes.clear();
+            }
+        });
+    }
+}
+
+#[cfg(feature = "gui")]
+impl PathSorter {
+    /// Lets strokes be drawn by dragging over `rect`, the area the image is painted in, and
+    /// draws the existing strokes on top of it.
+    pub fn edit_strokes(&mut self, ui: &Ui, rect: Rect) {
+        let image = ui.interact(rect, ui.id().with("path-strokes"), Sense::drag());
+
+        if image.drag_started() {
+            self.strokes.push(vec![]);
+        }
+        if image.dragged() {
+            let pointer = image.interact_pointer_pos();
+            if let (Some(pos), Some(stroke)) = (pointer, self.strokes.last_mut()) {
+                let point = [
+                    ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0),
+                    ((pos.y - rect.top()) / rect.height()).clamp(0.0, 1.0),
+                ];
+                //Skip points that barely moved, they only make the stroke jittery.
+                let moved = match stroke.last() {
+                    Some(last) => {
+                        let dx = (point[0] - last[0]) * rect.width();
+                        let dy = (point[1] - last[1]) * rect.height();
+                        dx * dx + dy * dy > 9.0
+                    }
+                    None => true,
+                };
+                if moved {
+                    stroke.push(point);
+                }
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        let to_screen = |[x, y]: [f32; 2]| {
+            Pos2::new(rect.left() + x * rect.width(), rect.top() + y * rect.height())
+        };
+        for stroke in &self.strokes {
+            let points = stroke.iter().copied().map(to_screen).collect();
+            painter.add(egui::Shape::line(points, (2.0, Color32::from_rgb(255, 200, 0))));
+        }
+    }
+}
+
+impl Animateable for PathSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        self.brush_width += (target.brush_width - self.brush_width) * weight;
+
+        //Strokes are paired up by the order they were drawn in, and resampled to the same
+        //number of points so every point has a partner to move towards.
+        for (stroke, target_stroke) in self.strokes.iter_mut().zip(&target.strokes) {
+            let count = stroke.len().max(target_stroke.len());
+            let target_points = resample(target_stroke, count);
+            *stroke = resample(stroke, count);
+            for (point, target_point) in stroke.iter_mut().zip(target_points) {
+                point[0] += (target_point[0] - point[0]) * weight;
+                point[1] += (target_point[1] - point[1]) * weight;
+            }
+        }
+    }
+}
+
+/// Unit normals at each point, perpendicular to the direction between its neighbours.
+fn vertex_normals(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+    (0..points.len())
+        .map(|i| {
+            let before = points[i.saturating_sub(1)];
+            let after = points[(i + 1).min(points.len() - 1)];
+            let [dx, dy] = normalize([after[0] - before[0], after[1] - before[1]]);
+            [-dy, dx]
+        })
+        .collect()
+}
+
+/// Positions every half pixel along the polyline, with the normal blended between points.
+fn walk<'a>(
+    points: &'a [[f32; 2]],
+    normals: &'a [[f32; 2]],
+) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + 'a {
+    points.windows(2).zip(normals.windows(2)).flat_map(|(p, n)| {
+        let steps = (distance(p[0], p[1]) * 2.0).ceil().max(1.0) as usize;
+        (0..steps).map(move |step| {
+            let t = step as f32 / steps as f32;
+            let position = lerp_point(p[0], p[1], t);
+            let normal = normalize(lerp_point(n[0], n[1], t));
+            (position, normal)
+        })
+    })
+}
+
+/// `count` points spread evenly by distance along the polyline.
+fn resample(points: &[[f32; 2]], count: usize) -> Vec<[f32; 2]> {
+    if points.len() < 2 || count < 2 {
+        return points.iter().copied().cycle().take(count).collect();
+    }
+
+    let mut distances = vec![0.0];
+    for pair in points.windows(2) {
+        distances.push(distances.last().unwrap() + distance(pair[0], pair[1]));
+    }
+    let total = *distances.last().unwrap();
+
+    let mut segment = 0;
+    (0..count)
+        .map(|i| {
+            let distance = total * i as f32 / (count - 1) as f32;
+            while segment < points.len() - 2 && distances[segment + 1] < distance {
+                segment += 1;
+            }
+            let length = distances[segment + 1] - distances[segment];
+            let t = if length > 0.0 {
+                ((distance - distances[segment]) / length).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            lerp_point(points[segment], points[segment + 1], t)
+        })
+        .collect()
+}
+
+fn lerp_point(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt()
+}
+
+fn normalize([x, y]: [f32; 2]) -> [f32; 2] {
+    let length = (x * x + y * y).sqrt();
+    if length > 0.0 {
+        [x / length, y / length]
+    } else {
+        [0.0, 0.0]
+    }
+}
@@ -0,0 +1,71 @@
+use std::f32::consts::TAU;
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Slider, Ui};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Sorts along rays going out from a center point.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RadialSorter {
+    /// Center of the rays, as a fraction of the image's width and height.
+    pub center: [f32; 2],
+    pub rays: u32,
+    /// Sort from the edges towards the center instead of outwards.
+    pub inwards: bool,
+}
+
+impl Default for RadialSorter {
+    fn default() -> Self {
+        RadialSorter {
+            center: [0.5, 0.5],
+            rays: 720,
+            inwards: false,
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for RadialSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let (center_x, center_y) = (self.center[0] * w as f32, self.center[1] * h as f32);
+        let rays = self.rays.max(1);
+
+        //Every pixel goes to the ray whose wedge it falls into, ordered by distance from the center.
+        let lines = lines_from_keys(w, h, |x, y| {
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = y as f32 + 0.5 - center_y;
+            let turn = dy.atan2(dx).rem_euclid(TAU) / TAU;
+            let ray = ((turn * rays as f32) as u32).min(rays - 1);
+            let distance = dx.hypot(dy);
+
+            Some((ray, if self.inwards { -distance } else { distance }))
+        });
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.add(Slider::new(&mut self.center[0], 0.0..=1.0).text("Center X"));
+        ui.add(Slider::new(&mut self.center[1], 0.0..=1.0).text("Center Y"));
+        ui.add(
+            Slider::new(&mut self.rays, 1..=4096)
+                .logarithmic(true)
+                .text("Number of rays"),
+        );
+        ui.checkbox(&mut self.inwards, "Sort towards the center?");
+    }
+}
+
+impl Animateable for RadialSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        for (center, target_center) in self.center.iter_mut().zip(target.center) {
+            *center += (target_center - *center) * weight;
+        }
+        self.rays = (self.rays as f32 + (target.rays as f32 - self.rays as f32) * weight).round() as u32;
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, rotated_band, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Slider, Ui};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Splits the image into connected regions of similar colour and sorts inside each region
+/// along an angle, so sorting stops at the edges of objects.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RegionSorter {
+    /// How far a pixel's colour may be from the average of its region, per channel.
+    pub tolerance: u8,
+    /// Angle to sort at inside every region, in degrees.
+    pub angle: f32,
+}
+
+impl Default for RegionSorter {
+    fn default() -> Self {
+        RegionSorter {
+            tolerance: 24,
+            angle: 0.0,
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for RegionSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let pixels = pixels_mut(image);
+        let regions = flood_regions(pixels, w, h, self.tolerance);
+
+        let (angle_sin, angle_cos) = self.angle.to_radians().sin_cos();
+        let lines = lines_from_keys(w, h, |x, y| {
+            let (band, along) = rotated_band(x as f32, y as f32, angle_sin, angle_cos);
+            Some(((regions[y * w + x], band), along))
+        });
+
+        sort_lines(pixels, &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.add(Slider::new(&mut self.tolerance, 0..=255).text("Colour tolerance"));
+        ui.add(Slider::new(&mut self.angle, 0.0..=360.0).text("Angle"));
+    }
+}
+
+impl Animateable for RegionSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        self.angle += (target.angle - self.angle) * weight;
+        let tolerance = self.tolerance as f32;
+        self.tolerance = (tolerance + (target.tolerance as f32 - tolerance) * weight).round() as u8;
+    }
+}
+
+/// Labels every pixel with the region it belongs to.
+///
+/// Regions grow by flood fill over the four neighbours of each pixel, taking in any pixel
+/// within `tolerance` of the region's running average colour.
+fn flood_regions(pixels: &[Pixel], w: usize, h: usize, tolerance: u8) -> Vec<usize> {
+    let mut regions = vec![usize::MAX; w * h];
+    let mut queue = VecDeque::new();
+    let mut region = 0;
+
+    for start in 0..w * h {
+        if regions[start] != usize::MAX {
+            continue;
+        }
+
+        regions[start] = region;
+        queue.push_back(start);
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+
+        while let Some(i) = queue.pop_front() {
+            for (channel, total) in sum.iter_mut().enumerate() {
+                *total += pixels[i][channel] as u64;
+            }
+            count += 1;
+            let average = sum.map(|total| total / count);
+
+            let (x, y) = (i % w, i / w);
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < w).then(|| i + 1),
+                (y > 0).then(|| i - w),
+                (y + 1 < h).then(|| i + w),
+            ];
+            for neighbour in neighbours.into_iter().flatten() {
+                if regions[neighbour] != usize::MAX {
+                    continue;
+                }
+                let close = (0..3).all(|channel| {
+                    let value = pixels[neighbour][channel] as u64;
+                    value.abs_diff(average[channel]) <= tolerance as u64
+                });
+                if close {
+                    regions[neighbour] = region;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        region += 1;
+    }
+
+    regions
+}
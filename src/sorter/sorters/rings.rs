@@ -0,0 +1,86 @@
+use std::f32::consts::TAU;
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Slider, Ui};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Sorts around concentric rings, treating each ring as one line.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RingSorter {
+    /// Center of the rings, as a fraction of the image's width and height.
+    pub center: [f32; 2],
+    /// Width of the rings relative to their height, 1.0 gives circles.
+    pub aspect_ratio: f32,
+    /// Distance between two rings in pixels, measured along the horizontal axis.
+    pub spacing: f32,
+    /// Where on the ring the sort starts, in degrees.
+    pub start_angle: f32,
+    pub counter_clockwise: bool,
+}
+
+impl Default for RingSorter {
+    fn default() -> Self {
+        RingSorter {
+            center: [0.5, 0.5],
+            aspect_ratio: 1.0,
+            spacing: 1.0,
+            start_angle: 0.0,
+            counter_clockwise: false,
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for RingSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let (center_x, center_y) = (self.center[0] * w as f32, self.center[1] * h as f32);
+        let aspect_ratio = self.aspect_ratio.max(0.01);
+        let spacing = self.spacing.max(1.0);
+        let start_angle = self.start_angle.to_radians();
+
+        //Squashing y turns every ellipse into a circle, so the ring is just the rounded radius.
+        let lines = lines_from_keys(w, h, |x, y| {
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = (y as f32 + 0.5 - center_y) * aspect_ratio;
+            let ring = (dx.hypot(dy) / spacing) as u32;
+
+            let mut turn = (dy.atan2(dx) - start_angle).rem_euclid(TAU);
+            if self.counter_clockwise {
+                turn = TAU - turn;
+            }
+
+            Some((ring, turn))
+        });
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.add(Slider::new(&mut self.center[0], 0.0..=1.0).text("Center X"));
+        ui.add(Slider::new(&mut self.center[1], 0.0..=1.0).text("Center Y"));
+        ui.add(
+            Slider::new(&mut self.aspect_ratio, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Aspect ratio"),
+        );
+        ui.add(Slider::new(&mut self.spacing, 1.0..=100.0).text("Ring spacing"));
+        ui.add(Slider::new(&mut self.start_angle, 0.0..=360.0).text("Start angle"));
+        ui.checkbox(&mut self.counter_clockwise, "Counter-clockwise?");
+    }
+}
+
+impl Animateable for RingSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        for (center, target_center) in self.center.iter_mut().zip(target.center) {
+            *center += (target_center - *center) * weight;
+        }
+        self.aspect_ratio += (target.aspect_ratio - self.aspect_ratio) * weight;
+        self.spacing += (target.spacing - self.spacing) * weight;
+        self.start_angle += (target.start_angle - self.start_angle) * weight;
+    }
+}
@@ -1,20 +1,97 @@
-use egui::{Color32, ColorImage, Ui};
+use std::fmt::{Display, Formatter};
 
-use crate::sorter::sorters::Sorter;
-use crate::sorter::SortMethod;
+#[cfg(feature = "gui")]
+use egui::Ui;
+use image::RgbaImage;
+
+use crate::sorter::sorters::{pixels_mut, Sorter};
+use crate::sorter::{Pixel, SortMethod};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ScanlineSorter {
+    /// Sort rows right to left instead of left to right.
+    pub reverse: bool,
+    #[serde(default)]
+    pub pattern: ScanPattern,
+}
+
+/// How the rows of a [`ScanlineSorter`] or the columns of a [`ColumnSorter`](super::ColumnSorter)
+/// are walked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanPattern {
+    /// Every line runs the same way.
+    #[default]
+    Straight,
+    /// Every other line runs the opposite way.
+    ZigZag,
+    /// Like zig-zag, but joined end to end into one line covering the whole image.
+    Snake,
+}
+
+impl ScanPattern {
+    pub const ALL: [ScanPattern; 3] = [
+        ScanPattern::Straight,
+        ScanPattern::ZigZag,
+        ScanPattern::Snake,
+    ];
 
-#[derive(Clone)]
-pub struct ScanlineSorter;
+    /// Whether the `index`th line should be walked backwards.
+    pub(crate) fn flips(&self, index: usize, reverse: bool) -> bool {
+        match self {
+            ScanPattern::Straight => reverse,
+            ScanPattern::ZigZag | ScanPattern::Snake => reverse ^ (index % 2 == 1),
+        }
+    }
 
-impl Sorter<Color32, &mut ColorImage, (), ()> for ScanlineSorter {
-    fn sort_image(&self, image: &mut ColorImage, sorter: impl SortMethod<Color32, ()>) -> () {
-        let pixels: &mut Vec<Color32> = image.pixels.as_mut();
-        let [w, _] = image.size;
-        pixels.par_chunks_exact_mut(w).for_each(|row| {
-            sorter.sort(row);
+    #[cfg(feature = "gui")]
+    pub(crate) fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            for pattern in ScanPattern::ALL {
+                ui.radio_value(self, pattern, pattern.to_string());
+            }
         });
     }
+}
+
+impl Display for ScanPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ScanPattern::Straight => "Straight",
+            ScanPattern::ZigZag => "Zig-zag",
+            ScanPattern::Snake => "Snake",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for ScanlineSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let w = image.width() as usize;
+        let pixels = pixels_mut(image);
+
+        //Flipping rows in place turns every pattern into plain left to right rows.
+        let flip_rows = |pixels: &mut [Pixel]| {
+            pixels.par_chunks_exact_mut(w).enumerate().for_each(|(y, row)| {
+                if self.pattern.flips(y, self.reverse) {
+                    row.reverse();
+                }
+            });
+        };
+
+        flip_rows(pixels);
+        if self.pattern == ScanPattern::Snake {
+            sorter.sort(pixels);
+        } else {
+            pixels.par_chunks_exact_mut(w).for_each(|row| sorter.sort(row));
+        }
+        flip_rows(pixels);
+    }
 
-    fn ui(&mut self, _ui: &mut Ui) {}
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.reverse, "Right to left?");
+        self.pattern.ui(ui);
+    }
 }
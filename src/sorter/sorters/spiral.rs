@@ -0,0 +1,130 @@
+use std::f32::consts::TAU;
+use std::fmt::{Display, Formatter};
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{ComboBox, Slider, Ui};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Sorts along a spiral winding out from a center point.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpiralSorter {
+    pub kind: SpiralKind,
+    /// Center of the spiral, as a fraction of the image's width and height.
+    pub center: [f32; 2],
+    /// Pixels between two turns for Archimedean spirals, growth per turn for logarithmic ones.
+    pub tightness: f32,
+    /// Rotation of the whole spiral, in degrees.
+    pub rotation: f32,
+    /// How many parallel lines fill the gap between two turns.
+    pub lanes: u32,
+    pub counter_clockwise: bool,
+    #[cfg(feature = "gui")]
+    #[serde(skip, default = "rand::random")]
+    id: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpiralKind {
+    #[default]
+    Archimedean,
+    Logarithmic,
+}
+
+impl Default for SpiralSorter {
+    fn default() -> Self {
+        SpiralSorter {
+            kind: SpiralKind::default(),
+            center: [0.5, 0.5],
+            tightness: 16.0,
+            rotation: 0.0,
+            lanes: 16,
+            counter_clockwise: false,
+            #[cfg(feature = "gui")]
+            id: rand::random(),
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for SpiralSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let (center_x, center_y) = (self.center[0] * w as f32, self.center[1] * h as f32);
+        let rotation = self.rotation.to_radians();
+        let lanes = self.lanes.max(1);
+
+        //`turns` is how many times the spiral has wound around by the time it reaches a pixel's
+        //radius. Its whole part picks the turn the pixel sits on and the fraction picks the lane.
+        let lines = lines_from_keys(w, h, |x, y| {
+            let dx = x as f32 + 0.5 - center_x;
+            let dy = y as f32 + 0.5 - center_y;
+
+            let mut angle = (dy.atan2(dx) - rotation).rem_euclid(TAU) / TAU;
+            if self.counter_clockwise {
+                angle = 1.0 - angle;
+            }
+
+            let radius = dx.hypot(dy);
+            let turns = match self.kind {
+                SpiralKind::Archimedean => radius / self.tightness.max(1.0),
+                SpiralKind::Logarithmic => radius.max(1.0).ln() / self.tightness.max(1.01).ln(),
+            } - angle;
+
+            let turn = turns.floor();
+            let lane = ((turns - turn) * lanes as f32) as u32;
+
+            Some((lane.min(lanes - 1), turn + angle))
+        });
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ComboBox::new(format!("spiral-{:?}", self.id), "Spiral")
+            .selected_text(self.kind.to_string())
+            .show_ui(ui, |ui| {
+                for kind in [SpiralKind::Archimedean, SpiralKind::Logarithmic] {
+                    ui.selectable_value(&mut self.kind, kind, kind.to_string());
+                }
+            });
+        ui.add(Slider::new(&mut self.center[0], 0.0..=1.0).text("Center X"));
+        ui.add(Slider::new(&mut self.center[1], 0.0..=1.0).text("Center Y"));
+        let tightness = match self.kind {
+            SpiralKind::Archimedean => Slider::new(&mut self.tightness, 1.0..=200.0)
+                .text("Distance between turns"),
+            SpiralKind::Logarithmic => Slider::new(&mut self.tightness, 1.01..=10.0)
+                .logarithmic(true)
+                .text("Growth per turn"),
+        };
+        ui.add(tightness);
+        ui.add(Slider::new(&mut self.rotation, 0.0..=360.0).text("Rotation"));
+        ui.add(Slider::new(&mut self.lanes, 1..=200).text("Lines per turn"));
+        ui.checkbox(&mut self.counter_clockwise, "Counter-clockwise?");
+    }
+}
+
+impl Animateable for SpiralSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        for (center, target_center) in self.center.iter_mut().zip(target.center) {
+            *center += (target_center - *center) * weight;
+        }
+        if self.kind == target.kind {
+            self.tightness += (target.tightness - self.tightness) * weight;
+        }
+        self.rotation += (target.rotation - self.rotation) * weight;
+    }
+}
+
+impl Display for SpiralKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SpiralKind::Archimedean => "Archimedean",
+            SpiralKind::Logarithmic => "Logarithmic",
+        };
+        write!(f, "{}", name)
+    }
+}
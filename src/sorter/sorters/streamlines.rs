@@ -0,0 +1,106 @@
+/// Traces lines that follow a direction field, starting a new line from every pixel that
+/// isn't part of one yet, so each pixel ends up in exactly one line.
+///
+/// `angles` holds the direction at every pixel in radians. The field is treated as an
+/// orientation, a step never turns back on the previous one, so fields that flip between
+/// `a` and `a + PI` still produce smooth lines.
+pub(crate) fn trace_streamlines(
+    w: usize,
+    h: usize,
+    angles: &[f32],
+    step: f32,
+    max_length: f32,
+) -> Vec<Vec<usize>> {
+    let step = step.max(0.1);
+    let tracer = Tracer {
+        w,
+        h,
+        angles,
+        step,
+        max_steps: (max_length.max(1.0) / step) as usize / 2,
+    };
+
+    let mut line_of = vec![usize::MAX; w * h];
+    let mut lines = Vec::new();
+
+    for start in 0..w * h {
+        if line_of[start] != usize::MAX {
+            continue;
+        }
+        let line_id = lines.len();
+        line_of[start] = line_id;
+
+        let (sin, cos) = angles[start].sin_cos();
+
+        //Walk backwards first so the finished line reads in the field's direction.
+        let mut line = tracer.trace(&mut line_of, line_id, start, (-cos, -sin));
+        line.reverse();
+        line.push(start);
+        line.extend(tracer.trace(&mut line_of, line_id, start, (cos, sin)));
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// How many pixels already claimed by other lines a line may cross before it ends.
+const MAX_SKIPPED: usize = 8;
+
+struct Tracer<'a> {
+    w: usize,
+    h: usize,
+    angles: &'a [f32],
+    step: f32,
+    max_steps: usize,
+}
+
+impl Tracer<'_> {
+    fn trace(
+        &self,
+        line_of: &mut [usize],
+        line_id: usize,
+        start: usize,
+        (mut dx, mut dy): (f32, f32),
+    ) -> Vec<usize> {
+        let (mut x, mut y) = ((start % self.w) as f32 + 0.5, (start / self.w) as f32 + 0.5);
+        let mut line = Vec::new();
+        let mut last = start;
+        let mut skipped = 0;
+
+        for _ in 0..self.max_steps {
+            x += dx * self.step;
+            y += dy * self.step;
+            if x < 0.0 || y < 0.0 || x >= self.w as f32 || y >= self.h as f32 {
+                break;
+            }
+
+            let i = (y as usize) * self.w + x as usize;
+            if i == last {
+                continue;
+            }
+            last = i;
+
+            //Neighbouring lines often clip each other's pixels, so hop over a few claimed pixels
+            //before giving up rather than stopping at the first one.
+            if line_of[i] != usize::MAX {
+                skipped += 1;
+                if skipped > MAX_SKIPPED {
+                    break;
+                }
+            } else {
+                skipped = 0;
+                line_of[i] = line_id;
+                line.push(i);
+            }
+
+            let (sin, cos) = self.angles[i].sin_cos();
+            (dx, dy) = if cos * dx + sin * dy < 0.0 {
+                (-cos, -sin)
+            } else {
+                (cos, sin)
+            };
+        }
+
+        line
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(feature = "gui")]
+use egui::Ui;
+use image::RgbaImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sorter::sorters::{pixels, pixels_mut, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+
+/// Pixel positions handled together, each chunk is sorted in place across every frame.
+const CHUNK_SIZE: usize = 4096;
+
+/// Sorts a stack of frames along time, every pixel position across all frames is one line.
+///
+/// All frames have to be the same size.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TemporalSorter {
+    /// Sort from the last frame towards the first.
+    pub reverse: bool,
+}
+
+impl Sorter<Pixel, &mut [RgbaImage], (), ()> for TemporalSorter {
+    fn sort_image(&self, frames: &mut [RgbaImage], mut sorter: impl SortMethod<Pixel, ()>) {
+        let Some(first) = frames.first() else {
+            return;
+        };
+        let pixel_count = (first.width() * first.height()) as usize;
+        let frame_count = frames.len();
+
+        for frame in frames.iter() {
+            sorter.prepare(pixels(frame));
+        }
+
+        //Every chunk of pixel positions gets a slice of every frame, so the chunks can be sorted
+        //in place and in parallel without copying the whole stack.
+        let mut chunks = (0..pixel_count.div_ceil(CHUNK_SIZE))
+            .map(|_| Vec::with_capacity(frame_count))
+            .collect::<Vec<_>>();
+        for frame in frames.iter_mut() {
+            let frame_chunks = pixels_mut(frame).chunks_mut(CHUNK_SIZE);
+            for (chunk, frame_chunk) in chunks.iter_mut().zip(frame_chunks) {
+                chunk.push(frame_chunk);
+            }
+        }
+
+        chunks.into_par_iter().for_each_init(
+            || Vec::with_capacity(frame_count),
+            |line: &mut Vec<Pixel>, mut frame_chunks: Vec<&mut [Pixel]>| {
+                for i in 0..frame_chunks[0].len() {
+                    line.clear();
+                    line.extend(frame_chunks.iter().map(|frame_chunk| frame_chunk[i]));
+                    if self.reverse {
+                        line.reverse();
+                        sorter.sort(line);
+                        line.reverse();
+                    } else {
+                        sorter.sort(line);
+                    }
+                    for (frame_chunk, pixel) in frame_chunks.iter_mut().zip(line.iter()) {
+                        frame_chunk[i] = *pixel;
+                    }
+                }
+            },
+        );
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.reverse, "Last frame to first?");
+    }
+}
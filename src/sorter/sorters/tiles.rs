@@ -0,0 +1,193 @@
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, rotated_band, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Color32, ComboBox, DragValue, Slider, TextEdit, Ui};
+use image::RgbaImage;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use rhai::{Engine, Scope, AST};
+use serde::{Deserialize, Serialize};
+
+/// Splits the image into square tiles that are each sorted at their own angle.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TileSorter {
+    /// Side length of a tile in pixels.
+    pub tile_size: u32,
+    pub pattern: TilePattern,
+    /// Angle added to every tile, in degrees.
+    pub angle: f32,
+    /// Seed for the random pattern, and for `random` in expressions.
+    pub seed: u64,
+    /// Rhai expression giving a tile's angle in degrees. It can use the tile's column `tx` and
+    /// row `ty`, the grid size `cols` and `rows`, and `random`, a seeded number in `0.0..1.0`.
+    pub expression: String,
+
+    #[cfg(feature = "gui")]
+    #[serde(skip, default = "rand::random")]
+    id: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TilePattern {
+    /// Every tile gets a random angle.
+    #[default]
+    Random,
+    /// Neighbouring tiles are a quarter turn apart.
+    Checkerboard,
+    /// Every tile's angle comes from `expression`.
+    Expression,
+}
+
+impl TilePattern {
+    pub const ALL: [TilePattern; 3] = [
+        TilePattern::Random,
+        TilePattern::Checkerboard,
+        TilePattern::Expression,
+    ];
+
+    #[cfg(feature = "gui")]
+    fn name(&self) -> &'static str {
+        match self {
+            TilePattern::Random => "Random",
+            TilePattern::Checkerboard => "Checkerboard",
+            TilePattern::Expression => "Expression",
+        }
+    }
+}
+
+impl Default for TileSorter {
+    fn default() -> Self {
+        TileSorter {
+            tile_size: 64,
+            pattern: TilePattern::Random,
+            angle: 0.0,
+            seed: 0,
+            expression: "(tx + ty) * 15".to_string(),
+            #[cfg(feature = "gui")]
+            id: rand::random(),
+        }
+    }
+}
+
+impl TileSorter {
+    /// The angle of every tile in row order, in degrees.
+    fn tile_angles(&self, cols: usize, rows: usize) -> Result<Vec<f32>, String> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let randoms = (0..cols * rows)
+            .map(|_| (rng.next_u32() >> 8) as f32 / (1 << 24) as f32)
+            .collect::<Vec<_>>();
+
+        let angles: Vec<f32> = match self.pattern {
+            TilePattern::Random => randoms.iter().map(|random| random * 360.0).collect(),
+            TilePattern::Checkerboard => (0..cols * rows)
+                .map(|i| if (i % cols + i / cols) % 2 == 1 { 90.0 } else { 0.0 })
+                .collect(),
+            TilePattern::Expression => {
+                let engine = Engine::new();
+                let ast = engine
+                    .compile_expression(&self.expression)
+                    .map_err(|error| error.to_string())?;
+                (0..cols * rows)
+                    .map(|i| {
+                        let (tx, ty) = (i % cols, i / cols);
+                        eval_angle(&engine, &ast, tx, ty, cols, rows, randoms[i])
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        Ok(angles.into_iter().map(|angle| angle + self.angle).collect())
+    }
+}
+
+fn eval_angle(
+    engine: &Engine,
+    ast: &AST,
+    tx: usize,
+    ty: usize,
+    cols: usize,
+    rows: usize,
+    random: f32,
+) -> Result<f32, String> {
+    let mut scope = Scope::new();
+    scope.push("tx", tx as i64);
+    scope.push("ty", ty as i64);
+    scope.push("cols", cols as i64);
+    scope.push("rows", rows as i64);
+    scope.push("random", random as f64);
+
+    let angle = engine
+        .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, ast)
+        .map_err(|error| error.to_string())?;
+    angle
+        .as_float()
+        .or_else(|_| angle.as_int().map(|angle| angle as f64))
+        .map(|angle| angle as f32)
+        .map_err(|type_name| format!("Expected a number, got {}", type_name))
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for TileSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let tile_size = self.tile_size.max(1) as usize;
+        let (cols, rows) = (w.div_ceil(tile_size), h.div_ceil(tile_size));
+
+        //A broken expression leaves the image alone, the error is shown in the ui.
+        let Ok(angles) = self.tile_angles(cols, rows) else {
+            return;
+        };
+        let directions = angles
+            .iter()
+            .map(|angle| angle.to_radians().sin_cos())
+            .collect::<Vec<_>>();
+
+        let lines = lines_from_keys(w, h, |x, y| {
+            let tile = (y / tile_size) * cols + x / tile_size;
+            let (sin, cos) = directions[tile];
+            let (band, along) = rotated_band(x as f32, y as f32, sin, cos);
+            Some(((tile, band), along))
+        });
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.add(
+            Slider::new(&mut self.tile_size, 2..=1024)
+                .logarithmic(true)
+                .text("Tile size"),
+        );
+        ComboBox::new(format!("tile-pattern-{:?}", self.id), "Tile angles")
+            .selected_text(self.pattern.name())
+            .show_ui(ui, |ui| {
+                for pattern in TilePattern::ALL {
+                    ui.selectable_value(&mut self.pattern, pattern, pattern.name());
+                }
+            });
+        ui.add(Slider::new(&mut self.angle, 0.0..=360.0).text("Angle"));
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut self.seed));
+            ui.label("Seed");
+        });
+
+        if self.pattern == TilePattern::Expression {
+            ui.add(
+                TextEdit::multiline(&mut self.expression)
+                    .code_editor()
+                    .desired_rows(2)
+                    .hint_text("Angle from tx, ty, cols, rows and random"),
+            );
+            if let Err(error) = self.tile_angles(1, 1) {
+                ui.colored_label(Color32::LIGHT_RED, error);
+            }
+        }
+    }
+}
+
+impl Animateable for TileSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        self.angle += (target.angle - self.angle) * weight;
+    }
+}
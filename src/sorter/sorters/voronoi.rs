@@ -0,0 +1,294 @@
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, rotated_band, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Color32, DragValue, Pos2, Rect, Sense, Slider, Stroke, Ui};
+use image::RgbaImage;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Splits the image into Voronoi cells and sorts each cell along its own angle.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VoronoiSorter {
+    pub cell_count: u32,
+    /// Seed the cells are scattered with. Changing it or the cell count in the ui scatters
+    /// the cells again.
+    pub seed: u64,
+    /// Angle every cell is sorted at before jitter, in degrees.
+    pub angle: f32,
+    /// How far each cell's angle may stray from `angle`, in degrees either way.
+    pub angle_jitter: f32,
+    /// The cells themselves, kept so they can be moved between keyframes.
+    pub cells: Vec<VoronoiCell>,
+
+    /// Cell being dragged around in the ui.
+    #[cfg(feature = "gui")]
+    #[serde(skip)]
+    dragged: Option<usize>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct VoronoiCell {
+    /// Position of the cell's seed, with `[1, 1]` the bottom right of the image.
+    pub position: [f32; 2],
+    /// Share of `angle_jitter` added to this cell's angle, in `-1.0..=1.0`.
+    pub jitter: f32,
+}
+
+impl Default for VoronoiSorter {
+    fn default() -> Self {
+        let mut sorter = VoronoiSorter {
+            cell_count: 32,
+            seed: 0,
+            angle: 0.0,
+            angle_jitter: 90.0,
+            cells: vec![],
+            #[cfg(feature = "gui")]
+            dragged: None,
+        };
+        sorter.scatter_cells();
+        sorter
+    }
+}
+
+impl VoronoiSorter {
+    /// Replaces the cells with `cell_count` new ones placed by `seed`.
+    pub fn scatter_cells(&mut self) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut unit = || (rng.next_u32() >> 8) as f32 / (1 << 24) as f32;
+
+        self.cells = (0..self.cell_count)
+            .map(|_| VoronoiCell {
+                position: [unit(), unit()],
+                jitter: unit() * 2.0 - 1.0,
+            })
+            .collect();
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for VoronoiSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        if self.cells.is_empty() {
+            return;
+        }
+
+        let cells = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let angle = self.angle + cell.jitter * self.angle_jitter;
+                let (sin, cos) = angle.to_radians().sin_cos();
+                ([cell.position[0] * w as f32, cell.position[1] * h as f32], sin, cos)
+            })
+            .collect::<Vec<_>>();
+
+        let seeds = cells.iter().map(|(seed, _, _)| *seed).collect::<Vec<_>>();
+        let grid = SeedGrid::new(&seeds, w, h);
+
+        let lines = lines_from_keys(w, h, |x, y| {
+            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+            let cell = grid.nearest(&seeds, [x, y])?;
+            let (_, sin, cos) = cells[cell];
+
+            let (band, along) = rotated_band(x, y, sin, cos);
+            Some(((cell, band), along))
+        });
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        let (cell_count, seed) = (self.cell_count, self.seed);
+        ui.add(
+            Slider::new(&mut self.cell_count, 1..=1000)
+                .logarithmic(true)
+                .text("Cells"),
+        );
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut self.seed));
+            ui.label("Seed");
+        });
+        if cell_count != self.cell_count || seed != self.seed {
+            self.scatter_cells();
+        }
+
+        ui.add(Slider::new(&mut self.angle, 0.0..=360.0).text("Angle"));
+        ui.add(Slider::new(&mut self.angle_jitter, 0.0..=180.0).text("Angle jitter"));
+        ui.label("Drag the dots on the image to move cells.");
+    }
+}
+
+/// Seeds sorted into square buckets about one seed apart, so finding the seed nearest to a pixel
+/// only has to look through the buckets around it rather than every seed.
+struct SeedGrid {
+    bucket_size: f32,
+    columns: usize,
+    rows: usize,
+    /// Indices of the seeds in each bucket, row by row.
+    buckets: Vec<Vec<usize>>,
+}
+
+impl SeedGrid {
+    fn new(seeds: &[[f32; 2]], w: usize, h: usize) -> Self {
+        let bucket_size = ((w * h) as f32 / seeds.len().max(1) as f32).sqrt().max(1.0);
+        let columns = (w as f32 / bucket_size).ceil().max(1.0) as usize;
+        let rows = (h as f32 / bucket_size).ceil().max(1.0) as usize;
+
+        let mut grid = SeedGrid {
+            bucket_size,
+            columns,
+            rows,
+            buckets: vec![vec![]; columns * rows],
+        };
+        for (i, &seed) in seeds.iter().enumerate() {
+            let (column, row) = grid.bucket(seed);
+            grid.buckets[row * columns + column].push(i);
+        }
+        grid
+    }
+
+    /// The bucket `point` falls in. Points off the image go in the nearest bucket on the edge,
+    /// which they're still further away from than anything on the image is.
+    fn bucket(&self, [x, y]: [f32; 2]) -> (usize, usize) {
+        let column = ((x / self.bucket_size) as usize).min(self.columns - 1);
+        let row = ((y / self.bucket_size) as usize).min(self.rows - 1);
+        (column, row)
+    }
+
+    /// The seed nearest to `point`, going for the first one on a tie. Looks through rings of
+    /// buckets around `point` until no bucket further out can hold anything closer.
+    fn nearest(&self, seeds: &[[f32; 2]], point: [f32; 2]) -> Option<usize> {
+        let (column, row) = self.bucket(point);
+        let (column, row) = (column as isize, row as isize);
+        let distance = |[x, y]: [f32; 2]| (point[0] - x).powi(2) + (point[1] - y).powi(2);
+
+        let mut nearest: Option<(f32, usize)> = None;
+        for ring in 0..=self.columns.max(self.rows) as isize {
+            for dy in -ring..=ring {
+                //Only the edge of the ring, the inside has been looked through already.
+                let step = if dy.abs() == ring { 1 } else { 2 * ring as usize };
+                for dx in (-ring..=ring).step_by(step) {
+                    let (x, y) = (column + dx, row + dy);
+                    if x < 0 || y < 0 || x >= self.columns as isize || y >= self.rows as isize {
+                        continue;
+                    }
+
+                    for &seed in &self.buckets[y as usize * self.columns + x as usize] {
+                        let seed_distance = distance(seeds[seed]);
+                        let closer = match nearest {
+                            Some((distance, nearest)) => {
+                                let order = seed_distance.total_cmp(&distance);
+                                order.then(seed.cmp(&nearest)).is_lt()
+                            }
+                            None => true,
+                        };
+                        if closer {
+                            nearest = Some((seed_distance, seed));
+                        }
+                    }
+                }
+            }
+
+            //Anything outside this ring is at least `ring` buckets away, give or take a pixel
+            //for rounding.
+            let reach = ring as f32 * self.bucket_size - 1.0;
+            if nearest.is_some_and(|(distance, _)| reach > 0.0 && distance < reach * reach) {
+                break;
+            }
+        }
+        nearest.map(|(_, seed)| seed)
+    }
+}
+
+#[cfg(feature = "gui")]
+impl VoronoiSorter {
+    /// Lets cells be dragged around on `rect`, the area the image is painted in, and marks
+    /// every cell on top of it.
+    pub fn edit_cells(&mut self, ui: &Ui, rect: Rect) {
+        let image = ui.interact(rect, ui.id().with("voronoi-cells"), Sense::drag());
+        let to_screen = |[x, y]: [f32; 2]| {
+            Pos2::new(rect.left() + x * rect.width(), rect.top() + y * rect.height())
+        };
+
+        if image.drag_started() {
+            self.dragged = image.interact_pointer_pos().and_then(|pos| {
+                self.cells
+                    .iter()
+                    .map(|cell| to_screen(cell.position).distance(pos))
+                    .enumerate()
+                    .filter(|&(_, distance)| distance < 10.0)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(i, _)| i)
+            });
+        }
+        if image.drag_released() {
+            self.dragged = None;
+        }
+        if let (Some(i), Some(pos)) = (self.dragged, image.interact_pointer_pos()) {
+            if let Some(cell) = self.cells.get_mut(i) {
+                cell.position = [
+                    ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0),
+                    ((pos.y - rect.top()) / rect.height()).clamp(0.0, 1.0),
+                ];
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        for cell in &self.cells {
+            painter.circle(
+                to_screen(cell.position),
+                4.0,
+                Color32::from_rgb(255, 200, 0),
+                Stroke::new(1.0, Color32::BLACK),
+            );
+        }
+    }
+}
+
+impl Animateable for VoronoiSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        self.angle += (target.angle - self.angle) * weight;
+        self.angle_jitter += (target.angle_jitter - self.angle_jitter) * weight;
+
+        //Cells are paired up by index, so keyframes that share a seed drift smoothly while
+        //different seeds fly across the image.
+        for (cell, target_cell) in self.cells.iter_mut().zip(&target.cells) {
+            for (position, target_position) in cell.position.iter_mut().zip(target_cell.position) {
+                *position += (target_position - *position) * weight;
+            }
+            cell.jitter += (target_cell.jitter - cell.jitter) * weight;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_grid_finds_the_nearest_seed() {
+        let (w, h) = (61, 37);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut unit = || (rng.next_u32() >> 8) as f32 / (1 << 24) as f32;
+        for count in [1, 2, 5, 40, 300] {
+            let mut seeds = (0..count)
+                .map(|_| [unit() * 1.2 * w as f32 - 6.0, unit() * 1.2 * h as f32 - 4.0])
+                .collect::<Vec<_>>();
+            //Ties go to the first seed.
+            seeds.push(seeds[0]);
+
+            let grid = SeedGrid::new(&seeds, w, h);
+            for (x, y) in (0..h).flat_map(|y| (0..w).map(move |x| (x, y))) {
+                let point = [x as f32 + 0.5, y as f32 + 0.5];
+                let distance =
+                    |[sx, sy]: [f32; 2]| (point[0] - sx).powi(2) + (point[1] - sy).powi(2);
+                let expected = (0..seeds.len())
+                    .min_by(|&a, &b| distance(seeds[a]).total_cmp(&distance(seeds[b])));
+                assert_eq!(grid.nearest(&seeds, point), expected, "{count} seeds at {x}, {y}");
+            }
+        }
+    }
+}
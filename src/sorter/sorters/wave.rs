@@ -0,0 +1,75 @@
+use std::f32::consts::TAU;
+
+use crate::sorter::animation::Animateable;
+use crate::sorter::sorters::{lines_from_keys, pixels_mut, rotate, sort_lines, Sorter};
+use crate::sorter::{Pixel, SortMethod};
+#[cfg(feature = "gui")]
+use egui::{Slider, Ui};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Sorts along parallel sine waves.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WaveSorter {
+    /// Direction the waves travel in, in degrees.
+    pub angle: f32,
+    /// Height of the waves in pixels.
+    pub amplitude: f32,
+    /// Length of one wave in pixels.
+    pub wavelength: f32,
+    /// Shift of the waves along their direction, in degrees of one wave.
+    pub phase: f32,
+}
+
+impl Default for WaveSorter {
+    fn default() -> Self {
+        WaveSorter {
+            angle: 0.0,
+            amplitude: 20.0,
+            wavelength: 200.0,
+            phase: 0.0,
+        }
+    }
+}
+
+impl Sorter<Pixel, &mut RgbaImage, (), ()> for WaveSorter {
+    fn sort_image(&self, image: &mut RgbaImage, sorter: impl SortMethod<Pixel, ()>) {
+        let (w, h) = (image.width() as usize, image.height() as usize);
+        let (angle_sin, angle_cos) = self.angle.to_radians().sin_cos();
+        let wavelength = self.wavelength.max(1.0);
+        let phase = self.phase.to_radians();
+
+        //Rotate into the wave's frame, then take the wave back out of the across coordinate so
+        //every wave becomes a straight line.
+        let lines = lines_from_keys(w, h, |x, y| {
+            let (x, y) = (x as f32, y as f32);
+            let (along, across) = rotate(x, y, angle_sin, angle_cos);
+            let wave = self.amplitude * (along / wavelength * TAU + phase).sin();
+
+            Some(((across - wave).floor() as i64, along))
+        });
+
+        sort_lines(pixels_mut(image), &lines, &sorter);
+    }
+
+    #[cfg(feature = "gui")]
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.add(Slider::new(&mut self.angle, 0.0..=360.0).text("Angle"));
+        ui.add(Slider::new(&mut self.amplitude, 0.0..=500.0).text("Amplitude"));
+        ui.add(
+            Slider::new(&mut self.wavelength, 1.0..=5000.0)
+                .logarithmic(true)
+                .text("Wavelength"),
+        );
+        ui.add(Slider::new(&mut self.phase, 0.0..=360.0).text("Phase"));
+    }
+}
+
+impl Animateable for WaveSorter {
+    fn lerp(&mut self, target: &Self, weight: f32) {
+        self.angle += (target.angle - self.angle) * weight;
+        self.amplitude += (target.amplitude - self.amplitude) * weight;
+        self.wavelength += (target.wavelength - self.wavelength) * weight;
+        self.phase += (target.phase - self.phase) * weight;
+    }
+}